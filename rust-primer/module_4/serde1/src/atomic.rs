@@ -0,0 +1,85 @@
+use std::fs::{self, File};
+use std::io::{Error, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Writing straight into the target (File::create truncates it first) means a crash half way
+// through leaves neither the old nor the new contents on disk. Instead we write a temporary file
+// next to the target and rename it over the target once it is safely on disk: rename is atomic
+// within a file system, so readers see either the old file or the new one, never a mix.
+
+/// The points at which `write_atomically_with_hook` hands control to its hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtomicWriteStep {
+    /// The temporary file exists and holds the new contents (possibly only in the OS cache)
+    Written,
+    /// The temporary file has been flushed and fsynced
+    Synced,
+    /// The temporary file has replaced the target
+    Renamed,
+    /// The directory entry for the rename has been fsynced
+    DirectorySynced,
+}
+
+/// Replaces the contents of `path` with `bytes` so that a crash leaves either the old or the new
+/// contents, never a torn file
+pub fn write_atomically<P: AsRef<Path>>(path: P, bytes: &[u8]) -> Result<(), Error> {
+    write_atomically_with_hook(path, bytes, |_| Ok(()))
+}
+
+/// Like `write_atomically`, but calls `hook` after every step. If the hook returns an error the
+/// write stops right there, which lets tests simulate a crash between any two steps.
+pub fn write_atomically_with_hook<P, F>(path: P, bytes: &[u8], mut hook: F) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(AtomicWriteStep) -> Result<(), Error>,
+{
+    let path = path.as_ref();
+    let tmp_path = temp_path_for(path);
+
+    let result = (|| {
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(bytes)?;
+        hook(AtomicWriteStep::Written)?;
+        tmp.flush()?;
+        tmp.sync_all()?;
+        hook(AtomicWriteStep::Synced)?;
+        fs::rename(&tmp_path, path)?;
+        hook(AtomicWriteStep::Renamed)
+    })();
+    if let Err(e) = result {
+        // Nothing to clean up if the rename already happened
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    sync_parent_dir(path)?;
+    hook(AtomicWriteStep::DirectorySynced)
+}
+
+/// Tells apart the temporary files of writes in the same process
+static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Temporary file in the same directory as `path` (rename only is atomic within a file system).
+/// Every call gets its own name, so concurrent writes to one target never share a temporary file.
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let id = NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{}.tmp.{}.{}", file_name, std::process::id(), id))
+}
+
+/// Makes the rename itself durable by fsyncing the directory that holds `path`
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<(), Error> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened (and fsynced) like files outside of unix
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
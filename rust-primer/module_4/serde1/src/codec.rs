@@ -0,0 +1,101 @@
+use std::fmt::Display;
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
+
+use crate::error::DecodeError;
+
+/// Byte order used when turning an integer into bytes (and back)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    /// Most significant byte first (network order)
+    Big,
+    /// Least significant byte first (x86, most ARM)
+    Little,
+    /// Whatever the machine running the program uses
+    Native,
+}
+
+/// An integer that knows how to serialize itself into a fixed number of bytes and into text.
+///
+/// Implemented for every primitive width from 8 to 128 bits, signed and unsigned.
+pub trait IntegerCodec: Copy + Display + FromStr<Err = ParseIntError> {
+    /// The fixed-size array holding the byte representation, e.g. `[u8; 4]` for `u32`
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default + Copy;
+
+    /// Number of bytes in the binary representation
+    const WIDTH: usize;
+
+    fn to_bytes(self, endianness: Endianness) -> Self::Bytes;
+
+    fn from_bytes(bytes: Self::Bytes, endianness: Endianness) -> Self;
+}
+
+macro_rules! impl_integer_codec {
+    ($($t:ty),*) => {
+        $(
+            impl IntegerCodec for $t {
+                type Bytes = [u8; std::mem::size_of::<$t>()];
+
+                const WIDTH: usize = std::mem::size_of::<$t>();
+
+                fn to_bytes(self, endianness: Endianness) -> Self::Bytes {
+                    match endianness {
+                        Endianness::Big => self.to_be_bytes(),
+                        Endianness::Little => self.to_le_bytes(),
+                        Endianness::Native => self.to_ne_bytes(),
+                    }
+                }
+
+                fn from_bytes(bytes: Self::Bytes, endianness: Endianness) -> Self {
+                    match endianness {
+                        Endianness::Big => <$t>::from_be_bytes(bytes),
+                        Endianness::Little => <$t>::from_le_bytes(bytes),
+                        Endianness::Native => <$t>::from_ne_bytes(bytes),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_codec!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Serializes any integer into its decimal string representation
+pub fn serialize_int_to_string<T: IntegerCodec>(data: T) -> String {
+    data.to_string()
+}
+
+/// Parses a decimal string produced by `serialize_int_to_string` back into an integer.
+///
+/// Surrounding whitespace (e.g. the newline an editor adds at the end of a file) is ignored.
+pub fn deserialize_int_from_string<T: IntegerCodec>(string: &str) -> Result<T, DecodeError> {
+    let trimmed = string.trim();
+    trimmed.parse::<T>().map_err(|e| match e.kind() {
+        IntErrorKind::Empty => DecodeError::Truncated,
+        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => DecodeError::Overflow,
+        _ => DecodeError::NotANumber(trimmed.to_string()),
+    })
+}
+
+/// Serializes any integer into bytes using the given byte order
+pub fn serialize_int_to_bytes<T: IntegerCodec>(data: T, endianness: Endianness) -> T::Bytes {
+    data.to_bytes(endianness)
+}
+
+/// Deserializes bytes produced by `serialize_int_to_bytes` with the same byte order
+pub fn deserialize_int_from_bytes<T: IntegerCodec>(bytes: T::Bytes, endianness: Endianness) -> T {
+    T::from_bytes(bytes, endianness)
+}
+
+/// Deserializes an integer from a slice that must hold exactly `T::WIDTH` bytes
+pub fn deserialize_int_from_slice<T: IntegerCodec>(bytes: &[u8], endianness: Endianness) -> Result<T, DecodeError> {
+    if bytes.len() < T::WIDTH {
+        return Err(DecodeError::Truncated);
+    }
+    if bytes.len() > T::WIDTH {
+        return Err(DecodeError::TrailingBytes(bytes.len() - T::WIDTH));
+    }
+    let mut array = T::Bytes::default();
+    array.as_mut().copy_from_slice(bytes);
+    Ok(T::from_bytes(array, endianness))
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+/// Everything that can go wrong when turning bytes (or text) back into a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ended before the value was complete
+    Truncated,
+    /// A varint used more bytes than the canonical (shortest) encoding
+    Overlong,
+    /// The encoded value does not fit in the target integer type
+    Overflow,
+    /// The value was complete but this many extra bytes followed it
+    TrailingBytes(usize),
+    /// The text is not a decimal number
+    NotANumber(String),
+    /// The input does not start with the expected magic bytes
+    BadMagic,
+    /// The input was written with a format version this code cannot read
+    UnsupportedVersion(u8),
+    /// The checksum stored in the input does not match its contents
+    ChecksumMismatch { stored: u32, computed: u32 },
+    /// A tagged value starts with a tag byte that names no known type
+    UnknownTag(u8),
+    /// A tagged bool holds something other than 0 or 1
+    InvalidBool(u8),
+    /// A tagged string is not valid UTF-8
+    InvalidUtf8,
+    /// A tagged value decoded fine but is not of the type the caller asked for (holds its type name)
+    UnexpectedType(&'static str),
+    /// Reading the input failed
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "input ended before the value was complete"),
+            DecodeError::Overlong => write!(f, "varint is not in its shortest form"),
+            DecodeError::Overflow => write!(f, "encoded value does not fit in the target type"),
+            DecodeError::TrailingBytes(n) => write!(f, "{} unexpected byte(s) after the value", n),
+            DecodeError::NotANumber(text) => write!(f, "{:?} is not a number", text),
+            DecodeError::BadMagic => write!(f, "magic bytes do not match"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            DecodeError::ChecksumMismatch { stored, computed } => {
+                write!(f, "checksum mismatch: stored {:08x}, computed {:08x}", stored, computed)
+            }
+            DecodeError::UnknownTag(tag) => write!(f, "unknown type tag {:#04x}", tag),
+            DecodeError::InvalidBool(byte) => write!(f, "{:#04x} is not a valid bool", byte),
+            DecodeError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            DecodeError::UnexpectedType(found) => write!(f, "unexpected value of type {}", found),
+            DecodeError::Io(kind) => write!(f, "I/O error: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
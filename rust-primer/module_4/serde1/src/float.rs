@@ -0,0 +1,81 @@
+use std::fmt::{Display, LowerExp};
+use std::num::ParseFloatError;
+use std::str::FromStr;
+
+use crate::codec::{Endianness, IntegerCodec, deserialize_int_from_slice};
+use crate::error::DecodeError;
+
+// Floats go to bytes through their IEEE-754 bit pattern, so every value, including NaN payloads,
+// signed zero and infinities, comes back bit for bit. Text can't promise that for NaN payloads
+// (all NaNs print as "NaN"), but for every other value it is exact as long as we print enough
+// digits. Rust's float formatting already prints the fewest digits that parse back to the same
+// value; we only pick between plain and exponent notation, whichever is shorter.
+
+/// A floating-point number that can be serialized into bytes (via its bits) and into text
+pub trait FloatCodec: Copy + Display + LowerExp + FromStr<Err = ParseFloatError> {
+    /// Unsigned integer of the same width holding the IEEE-754 bit pattern
+    type Bits: IntegerCodec;
+
+    fn to_bits(self) -> Self::Bits;
+
+    fn from_bits(bits: Self::Bits) -> Self;
+}
+
+impl FloatCodec for f32 {
+    type Bits = u32;
+
+    fn to_bits(self) -> u32 {
+        f32::to_bits(self)
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        f32::from_bits(bits)
+    }
+}
+
+impl FloatCodec for f64 {
+    type Bits = u64;
+
+    fn to_bits(self) -> u64 {
+        f64::to_bits(self)
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        f64::from_bits(bits)
+    }
+}
+
+/// Serializes a float into the shortest string that parses back to the same value
+pub fn serialize_float_to_string<T: FloatCodec>(data: T) -> String {
+    let plain = format!("{}", data);
+    let exponent = format!("{:e}", data);
+    if exponent.len() < plain.len() {
+        exponent
+    } else {
+        plain
+    }
+}
+
+/// Parses a string produced by `serialize_float_to_string` (surrounding whitespace is ignored)
+pub fn deserialize_float_from_string<T: FloatCodec>(string: &str) -> Result<T, DecodeError> {
+    let trimmed = string.trim();
+    if trimmed.is_empty() {
+        return Err(DecodeError::Truncated);
+    }
+    trimmed.parse::<T>().map_err(|_| DecodeError::NotANumber(trimmed.to_string()))
+}
+
+/// Serializes a float into the bytes of its IEEE-754 representation
+pub fn serialize_float_to_bytes<T: FloatCodec>(data: T, endianness: Endianness) -> <T::Bits as IntegerCodec>::Bytes {
+    data.to_bits().to_bytes(endianness)
+}
+
+/// Deserializes bytes produced by `serialize_float_to_bytes` with the same byte order
+pub fn deserialize_float_from_bytes<T: FloatCodec>(bytes: <T::Bits as IntegerCodec>::Bytes, endianness: Endianness) -> T {
+    T::from_bits(T::Bits::from_bytes(bytes, endianness))
+}
+
+/// Deserializes a float from a slice that must hold exactly as many bytes as the float is wide
+pub fn deserialize_float_from_slice<T: FloatCodec>(bytes: &[u8], endianness: Endianness) -> Result<T, DecodeError> {
+    deserialize_int_from_slice::<T::Bits>(bytes, endianness).map(T::from_bits)
+}
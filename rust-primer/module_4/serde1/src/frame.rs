@@ -0,0 +1,79 @@
+use std::io::{Error, ErrorKind};
+
+use crate::crc32::crc32;
+use crate::error::DecodeError;
+
+// A framed file wraps a payload so the reader can tell a real value from garbage:
+//
+//   magic   "SRD1"      4 bytes
+//   version             1 byte
+//   length  (u32, BE)   4 bytes   number of payload bytes
+//   payload             length bytes
+//   crc32   (u32, BE)   4 bytes   checksum of everything before it
+//
+// A bit flip anywhere changes the checksum, and a truncated write either cuts the trailer or
+// leaves fewer bytes than the length field promises.
+
+/// Identifies a framed file
+pub const FRAME_MAGIC: [u8; 4] = *b"SRD1";
+
+/// The only layout version this code knows how to read
+pub const FRAME_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = FRAME_MAGIC.len() + 1 + 4;
+const TRAILER_LEN: usize = 4;
+
+/// Wraps `payload` in a frame with header and checksum. Fails with `InvalidInput` if the payload
+/// is too long for the length field (4 GiB or more).
+pub fn serialize_to_frame(payload: &[u8]) -> Result<Vec<u8>, Error> {
+    let length = u32::try_from(payload.len())
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "frame payload larger than 4 GiB"))?;
+    Ok(build_frame(payload, length))
+}
+
+/// Frames a payload whose length the caller already knows fits in a u32
+pub(crate) fn build_frame(payload: &[u8], length: u32) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len() + TRAILER_LEN);
+    bytes.extend_from_slice(&FRAME_MAGIC);
+    bytes.push(FRAME_VERSION);
+    bytes.extend_from_slice(&length.to_be_bytes());
+    bytes.extend_from_slice(payload);
+    let checksum = crc32(&bytes);
+    bytes.extend_from_slice(&checksum.to_be_bytes());
+    bytes
+}
+
+/// Validates a frame and returns the payload it holds
+pub fn deserialize_from_frame(bytes: &[u8]) -> Result<&[u8], DecodeError> {
+    if bytes.len() < FRAME_MAGIC.len() {
+        return Err(DecodeError::Truncated);
+    }
+    if bytes[..FRAME_MAGIC.len()] != FRAME_MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    if bytes.len() < HEADER_LEN {
+        return Err(DecodeError::Truncated);
+    }
+    let version = bytes[FRAME_MAGIC.len()];
+    if version != FRAME_VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    let length_bytes: [u8; 4] = bytes[FRAME_MAGIC.len() + 1..HEADER_LEN].try_into().unwrap();
+    let length = u32::from_be_bytes(length_bytes) as usize;
+
+    let expected_len = HEADER_LEN + length + TRAILER_LEN;
+    if bytes.len() < expected_len {
+        return Err(DecodeError::Truncated);
+    }
+    if bytes.len() > expected_len {
+        return Err(DecodeError::TrailingBytes(bytes.len() - expected_len));
+    }
+
+    let (framed, trailer) = bytes.split_at(HEADER_LEN + length);
+    let stored = u32::from_be_bytes(trailer.try_into().unwrap());
+    let computed = crc32(framed);
+    if stored != computed {
+        return Err(DecodeError::ChecksumMismatch { stored, computed });
+    }
+    Ok(&framed[HEADER_LEN..])
+}
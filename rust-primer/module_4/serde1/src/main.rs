@@ -1,75 +1,341 @@
 use std::io::Error;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Write};
+use std::io::{BufReader, Read};
+use std::process;
 
-use solution::{serialize_to_string, serialize_to_bytes, deserialize_from_bytes};
+use solution::{DecodeError, Endianness,
+               serialize_int_to_string, deserialize_int_from_string,
+               serialize_int_to_bytes, deserialize_int_from_slice,
+               serialize_to_varint, deserialize_from_varint,
+               serialize_to_framed_bytes, deserialize_from_framed_bytes,
+               Value, serialize_tagged, deserialize_tagged,
+               write_atomically};
 
-fn write_bytes_to_file(bytes: [u8; 4], filename: &str) -> Result<(), Error> {
-    // Create a File; see Rust doc for std::fs::File
-    let mut buffer = File::create(filename)?;
-    // Write bytes to that file
-    buffer.write_all(&bytes)?;
-    Ok(())
+const USAGE: &str = "\
+Usage:
+    main encode <value> [--format text|binary|varint|framed|tagged] [--endian big|little|native] [--output <file>]
+    main decode [--format text|binary|varint|framed|tagged] [--endian big|little|native] [--input <file>]
+    main inspect <file>
+
+The value is a u32. The format defaults to binary, the byte order to big endian, and the file
+to test.txt for text and test.bytes otherwise.";
+
+/// How the integer is laid out in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Binary,
+    Varint,
+    Framed,
+    Tagged,
+}
+
+impl Format {
+    fn default_filename(&self) -> &'static str {
+        match self {
+            Format::Text => "test.txt",
+            Format::Binary | Format::Varint | Format::Framed | Format::Tagged => "test.bytes",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    Encode { value: u32, format: Format, endianness: Endianness, filename: String },
+    Decode { format: Format, endianness: Endianness, filename: String },
+    Inspect { filename: String },
+}
+
+fn parse_format(arg: &str) -> Result<Format, String> {
+    match arg {
+        "text" => Ok(Format::Text),
+        "binary" => Ok(Format::Binary),
+        "varint" => Ok(Format::Varint),
+        "framed" => Ok(Format::Framed),
+        "tagged" => Ok(Format::Tagged),
+        _ => Err(format!("unknown format {:?}", arg)),
+    }
+}
+
+fn parse_endianness(arg: &str) -> Result<Endianness, String> {
+    match arg {
+        "big" => Ok(Endianness::Big),
+        "little" => Ok(Endianness::Little),
+        "native" => Ok(Endianness::Native),
+        _ => Err(format!("unknown byte order {:?}", arg)),
+    }
+}
+
+/// Turns the command line (without the program name) into a `Command`
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (subcommand, rest) = args.split_first().ok_or("missing subcommand")?;
+
+    let mut positional = Vec::new();
+    let mut format = Format::Binary;
+    let mut endianness = Endianness::Big;
+    let mut filename = None;
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        let mut flag_value = || iter.next().ok_or(format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--format" => format = parse_format(flag_value()?)?,
+            "--endian" => endianness = parse_endianness(flag_value()?)?,
+            "--output" | "--input" => filename = Some(flag_value()?.clone()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {:?}", arg)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+    let filename = filename.unwrap_or_else(|| format.default_filename().to_string());
+
+    match (subcommand.as_str(), positional.as_slice()) {
+        ("encode", [value]) => {
+            let value = deserialize_int_from_string(value)
+                .map_err(|e| format!("invalid value {:?}: {}", value, e))?;
+            Ok(Command::Encode { value, format, endianness, filename })
+        }
+        ("decode", []) => Ok(Command::Decode { format, endianness, filename }),
+        ("inspect", [filename]) => Ok(Command::Inspect { filename: filename.clone() }),
+        ("encode" | "decode" | "inspect", _) => Err(format!("wrong number of arguments for {}", subcommand)),
+        _ => Err(format!("unknown subcommand {:?}", subcommand)),
+    }
+}
+
+fn write_bytes_to_file(bytes: &[u8], filename: &str) -> Result<(), Error> {
+    // Write to a temporary file and rename it over the target, so a crash never leaves a torn file
+    write_atomically(filename, bytes)
 }
 
-fn read_bytes_from_file(filename: &str) -> [u8; 4] {
-    let f = File::open(filename).expect("could not open file");
+fn read_bytes_from_file(filename: &str) -> Result<Vec<u8>, Error> {
+    let f = File::open(filename)?;
     let mut reader = BufReader::new(f);
     let mut buffer = Vec::new();
 
-    // Read file into vector.
-    reader.read_to_end(&mut buffer).expect("error while reading file");
-
-    // Transform Vec (much preferred way of handling collection of values) into array (for this example)
-    let array = vec_to_array(buffer);
-    array
+    // Read file into vector. Whether it holds a valid integer is for the deserializer to decide
+    reader.read_to_end(&mut buffer)?;
+    Ok(buffer)
 }
 
-fn write_string_to_file(string: &str, filename: &str) {
-    let f = File::create(filename).expect("error creating file");
-    let mut f = BufWriter::new(f);
-    f.write_all(string.as_bytes());
+fn write_string_to_file(string: &str, filename: &str) -> Result<(), Error> {
+    write_atomically(filename, string.as_bytes())
 }
 
-fn read_string_from_file(filename: &str) -> String {
+fn read_string_from_file(filename: &str) -> Result<String, Error> {
     let mut data = String::new();
-    let f = File::open(filename).expect("error while opening file");
+    let f = File::open(filename)?;
     let mut br = BufReader::new(f);
-    br.read_to_string(&mut data);
-    data
+    br.read_to_string(&mut data)?;
+    Ok(data)
+}
+
+/// Decodes a varint that must span the whole input and fit in a u32
+fn decode_varint_u32(bytes: &[u8]) -> Result<u32, DecodeError> {
+    let (value, len) = deserialize_from_varint(bytes)?;
+    if len < bytes.len() {
+        return Err(DecodeError::TrailingBytes(bytes.len() - len));
+    }
+    u32::try_from(value).map_err(|_| DecodeError::Overflow)
+}
+
+/// Decodes a file holding exactly one tagged u32
+fn decode_tagged_u32(bytes: &[u8]) -> Result<u32, DecodeError> {
+    let (value, len) = deserialize_tagged(bytes)?;
+    if len < bytes.len() {
+        return Err(DecodeError::TrailingBytes(bytes.len() - len));
+    }
+    match value {
+        Value::U32(integer) => Ok(integer),
+        other => Err(DecodeError::UnexpectedType(other.type_name())),
+    }
+}
+
+fn encode(value: u32, format: Format, endianness: Endianness, filename: &str) -> Result<(), String> {
+    let written = match format {
+        Format::Text => write_string_to_file(&serialize_int_to_string(value), filename),
+        Format::Binary => write_bytes_to_file(&serialize_int_to_bytes(value, endianness), filename),
+        Format::Varint => write_bytes_to_file(&serialize_to_varint(value as u64), filename),
+        Format::Framed => write_bytes_to_file(&serialize_to_framed_bytes(value), filename),
+        Format::Tagged => write_bytes_to_file(&serialize_tagged(&Value::U32(value)), filename),
+    };
+    written.map_err(|e| format!("could not write {}: {}", filename, e))?;
+    println!("Wrote {} to {} ({:?})", value, filename, format);
+    Ok(())
+}
+
+fn decode(format: Format, endianness: Endianness, filename: &str) -> Result<(), String> {
+    let io_error = |e: Error| format!("could not read {}: {}", filename, e);
+    let decoded = match format {
+        Format::Text => deserialize_int_from_string::<u32>(&read_string_from_file(filename).map_err(io_error)?),
+        Format::Binary => deserialize_int_from_slice::<u32>(&read_bytes_from_file(filename).map_err(io_error)?, endianness),
+        Format::Varint => decode_varint_u32(&read_bytes_from_file(filename).map_err(io_error)?),
+        Format::Framed => deserialize_from_framed_bytes(&read_bytes_from_file(filename).map_err(io_error)?),
+        Format::Tagged => decode_tagged_u32(&read_bytes_from_file(filename).map_err(io_error)?),
+    };
+    let value = decoded.map_err(|e| format!("could not decode {}: {}", filename, e))?;
+    println!("The ({:?}) deserialized integer is: {}", format, value);
+    Ok(())
 }
 
-fn vec_to_array<T, const N: usize>(v: Vec<T>) -> [T; N] {
-    v.try_into()
-        .unwrap_or_else(|v: Vec<T>| panic!("Expected a Vec of length {} but it was {}", N, v.len()))
+/// Shows the raw bytes of a file and every way they could be interpreted
+fn inspect(filename: &str) -> Result<(), String> {
+    let bytes = read_bytes_from_file(filename).map_err(|e| format!("could not read {}: {}", filename, e))?;
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    println!("{}: {} byte(s): {}", filename, bytes.len(), hex.join(" "));
+
+    let show = |name: &str, decoded: Result<u32, DecodeError>| match decoded {
+        Ok(value) => println!("  as {:<14} {}", name, value),
+        Err(e) => println!("  as {:<14} <{}>", name, e),
+    };
+    show("text", deserialize_int_from_string(&String::from_utf8_lossy(&bytes)));
+    show("big endian", deserialize_int_from_slice(&bytes, Endianness::Big));
+    show("little endian", deserialize_int_from_slice(&bytes, Endianness::Little));
+    show("varint", decode_varint_u32(&bytes));
+    show("framed", deserialize_from_framed_bytes(&bytes));
+
+    // A tagged file describes itself, so it can hold more than a single u32
+    match solution::inspect(&bytes) {
+        Ok(listing) => {
+            println!("  as tagged values:");
+            for line in listing.lines() {
+                println!("    {}", line);
+            }
+        }
+        Err(e) => println!("  as tagged values <{}>", e),
+    }
+    Ok(())
+}
+
+/// Why a run failed, which decides the exit code
+#[derive(Debug)]
+enum Failure {
+    /// The command line made no sense; exit code 2
+    Usage(String),
+    /// A file could not be read, written or decoded; exit code 1
+    Failed(String),
+}
+
+impl Failure {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Failure::Usage(_) => 2,
+            Failure::Failed(_) => 1,
+        }
+    }
+}
+
+/// Parses the command line (without the program name) and runs the command
+fn run(args: &[String]) -> Result<(), Failure> {
+    let command = parse_args(args).map_err(Failure::Usage)?;
+    let result = match command {
+        Command::Encode { value, format, endianness, filename } => encode(value, format, endianness, &filename),
+        Command::Decode { format, endianness, filename } => decode(format, endianness, &filename),
+        Command::Inspect { filename } => inspect(&filename),
+    };
+    result.map_err(Failure::Failed)
 }
 
 fn main() {
-    println!("Basic Integer serialization, deserialization");
-    // Change this variable to serialize data (true) or deserialize it (false)
-    let serialize = true;
-    let bytes_filename = "test.bytes";
-    let string_filename = "test.txt";
-    let integer: u32 = 33;
-    if serialize {
-        // We obtain a human-readable representation of the data (integer) to store
-        let integer_in_string = serialize_to_string(integer);
-        // Then we store the string into a file
-        write_string_to_file(&integer_in_string, &string_filename);
-
-        // We obtain a byte representation of the data (integer) to store
-        let integer_in_bytes = serialize_to_bytes(integer);
-        // Then we store the byte representation on a file on disk
-        write_bytes_to_file(integer_in_bytes, &bytes_filename);
-    }
-    else {
-        // We read string from file (we can deserialize it directly into a rust string)
-        let data = read_string_from_file(&string_filename);
-        println!("The (string) deserialized integer is: {}", data);
-
-        // We read bytes from a file
-        let read_bytes = read_bytes_from_file(&bytes_filename);
-        let deserialized_integer = deserialize_from_bytes(read_bytes);
-        println!("The (bytes) deserialized integer is: {}", deserialized_integer);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(failure) = run(&args) {
+        match &failure {
+            Failure::Usage(e) => eprintln!("error: {}\n\n{}", e, USAGE),
+            Failure::Failed(e) => eprintln!("error: {}", e),
+        }
+        process::exit(failure.exit_code());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    /// A path in the system temp directory that is unique to this test process
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("serde1_main_{}_{}", name, process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn exit_code(line: &str) -> Option<i32> {
+        run(&args(line)).err().map(|failure| failure.exit_code())
+    }
+
+    #[test]
+    fn test_parse_encode_decode_inspect() {
+        match parse_args(&args("encode 33")).unwrap() {
+            Command::Encode { value, format, endianness, filename } => {
+                assert_eq!((value, format, endianness), (33, Format::Binary, Endianness::Big));
+                assert_eq!(filename, "test.bytes");
+            }
+            other => panic!("parsed {:?}", other),
+        }
+        match parse_args(&args("encode --format text 7 --endian little")).unwrap() {
+            Command::Encode { value, format, endianness, filename } => {
+                assert_eq!((value, format, endianness), (7, Format::Text, Endianness::Little));
+                assert_eq!(filename, "test.txt");
+            }
+            other => panic!("parsed {:?}", other),
+        }
+        match parse_args(&args("decode --format varint --input in.bin")).unwrap() {
+            Command::Decode { format, filename, .. } => assert_eq!((format, filename.as_str()), (Format::Varint, "in.bin")),
+            other => panic!("parsed {:?}", other),
+        }
+        match parse_args(&args("inspect some.bytes")).unwrap() {
+            Command::Inspect { filename } => assert_eq!(filename, "some.bytes"),
+            other => panic!("parsed {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_bad_subcommand() {
+        assert_eq!(parse_args(&[]).unwrap_err(), "missing subcommand");
+        assert!(parse_args(&args("serialize 33")).unwrap_err().contains("unknown subcommand"));
+        assert!(parse_args(&args("--format text")).unwrap_err().contains("unknown subcommand"));
+    }
+
+    #[test]
+    fn test_parse_missing_operands() {
+        assert!(parse_args(&args("encode")).unwrap_err().contains("wrong number of arguments"));
+        assert!(parse_args(&args("encode 1 2")).unwrap_err().contains("wrong number of arguments"));
+        assert!(parse_args(&args("decode extra")).unwrap_err().contains("wrong number of arguments"));
+        assert!(parse_args(&args("inspect")).unwrap_err().contains("wrong number of arguments"));
+        assert!(parse_args(&args("encode 33 --format")).unwrap_err().contains("expects a value"));
+        assert!(parse_args(&args("decode --output")).unwrap_err().contains("expects a value"));
+    }
+
+    #[test]
+    fn test_parse_bad_values() {
+        assert!(parse_args(&args("encode 33 --format yaml")).unwrap_err().contains("unknown format"));
+        assert!(parse_args(&args("encode 33 --endian middle")).unwrap_err().contains("unknown byte order"));
+        assert!(parse_args(&args("encode 33 --verbose")).unwrap_err().contains("unknown option"));
+        assert!(parse_args(&args("encode -1")).unwrap_err().contains("invalid value"));
+        assert!(parse_args(&args("encode 4294967296")).unwrap_err().contains("invalid value"));
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        // Usage errors exit with 2, before touching any file
+        assert_eq!(exit_code("frobnicate"), Some(2));
+        assert_eq!(exit_code("encode"), Some(2));
+        assert_eq!(exit_code("decode --format yaml"), Some(2));
+
+        // I/O and decode errors exit with 1
+        let missing = temp_path("missing");
+        let _ = std::fs::remove_file(&missing);
+        assert_eq!(exit_code(&format!("decode --input {}", missing)), Some(1));
+        assert_eq!(exit_code(&format!("inspect {}", missing)), Some(1));
+
+        let path = temp_path("exit_codes");
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+        assert_eq!(exit_code(&format!("decode --input {}", path)), Some(1));
+        assert_eq!(exit_code(&format!("decode --format framed --input {}", path)), Some(1));
+
+        assert_eq!(exit_code(&format!("encode 33 --output {}", path)), None);
+        assert_eq!(exit_code(&format!("decode --input {}", path)), None);
+        std::fs::remove_file(&path).unwrap();
     }
 }
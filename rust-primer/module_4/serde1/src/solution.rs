@@ -1,19 +1,65 @@
-use std::io::Error;
-use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Write};
+pub mod atomic;
+pub mod codec;
+pub mod crc32;
+pub mod error;
+pub mod float;
+pub mod frame;
+pub mod stream;
+pub mod tagged;
+pub mod text;
+pub mod varint;
+
+pub use atomic::{AtomicWriteStep, write_atomically, write_atomically_with_hook};
+pub use codec::{Endianness, IntegerCodec,
+                serialize_int_to_string, deserialize_int_from_string,
+                serialize_int_to_bytes, deserialize_int_from_bytes, deserialize_int_from_slice};
+pub use crc32::crc32;
+pub use error::DecodeError;
+pub use float::{FloatCodec,
+                serialize_float_to_string, deserialize_float_from_string,
+                serialize_float_to_bytes, deserialize_float_from_bytes, deserialize_float_from_slice};
+pub use frame::{FRAME_MAGIC, FRAME_VERSION, serialize_to_frame, deserialize_from_frame};
+pub use stream::{Encoder, Decoder};
+pub use tagged::{Value, serialize_tagged, serialize_tagged_into, serialize_all_tagged,
+                 deserialize_tagged, deserialize_all_tagged, inspect};
+pub use text::{TextFormat, TEXT_FORMATS, serialize_int_to_text, deserialize_int_from_text};
+pub use varint::{serialize_to_varint, deserialize_from_varint,
+                 serialize_to_zigzag, deserialize_from_zigzag,
+                 zigzag_encode, zigzag_decode};
 
 // Serializes an integer into a string
 // 1. what's the difference between casting into a string and serializing into a string?
 pub fn serialize_to_string(data: u32) -> String {
-    panic!("TODO milestone primer-mod5");
+    serialize_int_to_string(data)
 }
 
 /// Serializes an integer into bytes
 pub fn serialize_to_bytes(data: u32) -> [u8; 4] {
-    panic!("TODO milestone primer-mod5");
+    serialize_int_to_bytes(data, Endianness::Big)
 }
 
 /// Reads the contents of a file and deserializes them into an integer
 pub fn deserialize_from_bytes(bytes:  [u8; 4]) -> u32 {
-    panic!("TODO milestone primer-mod5");
+    deserialize_int_from_bytes(bytes, Endianness::Big)
+}
+
+/// Deserializes a big-endian integer from a slice of exactly 4 bytes, e.g. the contents of a file
+pub fn deserialize_from_slice(bytes: &[u8]) -> Result<u32, DecodeError> {
+    deserialize_int_from_slice(bytes, Endianness::Big)
+}
+
+/// Parses the string produced by `serialize_to_string`
+pub fn deserialize_from_string(string: &str) -> Result<u32, DecodeError> {
+    deserialize_int_from_string(string)
+}
+
+/// Serializes an integer into big-endian bytes wrapped in a checksummed frame
+pub fn serialize_to_framed_bytes(data: u32) -> Vec<u8> {
+    let payload = serialize_to_bytes(data);
+    frame::build_frame(&payload, payload.len() as u32)
+}
+
+/// Validates a frame produced by `serialize_to_framed_bytes` and deserializes the integer in it
+pub fn deserialize_from_framed_bytes(bytes: &[u8]) -> Result<u32, DecodeError> {
+    deserialize_from_slice(deserialize_from_frame(bytes)?)
 }
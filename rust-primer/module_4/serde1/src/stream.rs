@@ -0,0 +1,96 @@
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Write};
+
+use crate::codec::{Endianness, IntegerCodec};
+use crate::error::DecodeError;
+
+// A stream is just fixed-width integers back to back, with no header and no count, so a writer
+// can keep appending and a reader can stop wherever it likes. Both sides buffer, so writing or
+// reading a million values costs a few hundred system calls instead of a million, and neither
+// ever holds more than one buffer's worth of data in memory.
+
+/// Writes an unbounded sequence of `u32` values to `W`
+pub struct Encoder<W: Write> {
+    writer: BufWriter<W>,
+    endianness: Endianness,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Creates an encoder that writes big-endian values
+    pub fn new(writer: W) -> Self {
+        Encoder::with_endianness(writer, Endianness::Big)
+    }
+
+    pub fn with_endianness(writer: W, endianness: Endianness) -> Self {
+        Encoder {
+            writer: BufWriter::new(writer),
+            endianness,
+        }
+    }
+
+    /// Appends one value to the stream
+    pub fn encode(&mut self, value: u32) -> Result<(), Error> {
+        self.writer.write_all(&value.to_bytes(self.endianness))
+    }
+
+    /// Flushes everything still buffered and hands back the underlying writer.
+    ///
+    /// Always call this: dropping the encoder also flushes, but any error is silently lost.
+    pub fn finish(self) -> Result<W, Error> {
+        self.writer.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+/// Reads the values written by an `Encoder`, one at a time, as an iterator
+pub struct Decoder<R: Read> {
+    reader: BufReader<R>,
+    endianness: Endianness,
+    done: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    /// Creates a decoder that reads big-endian values
+    pub fn new(reader: R) -> Self {
+        Decoder::with_endianness(reader, Endianness::Big)
+    }
+
+    pub fn with_endianness(reader: R, endianness: Endianness) -> Self {
+        Decoder {
+            reader: BufReader::new(reader),
+            endianness,
+            done: false,
+        }
+    }
+
+    /// Reads the next value; `Ok(None)` means the stream ended cleanly between two values
+    fn read_value(&mut self) -> Result<Option<u32>, DecodeError> {
+        let mut bytes = [0u8; 4];
+        let mut filled = 0;
+        while filled < bytes.len() {
+            match self.reader.read(&mut bytes[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(DecodeError::Truncated),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(DecodeError::Io(e.kind())),
+            }
+        }
+        Ok(Some(u32::from_bytes(bytes, self.endianness)))
+    }
+}
+
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = Result<u32, DecodeError>;
+
+    /// Yields every value in the stream. After the first error the iterator is over: the
+    /// position in the stream is no longer trustworthy.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_value().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
@@ -0,0 +1,243 @@
+use std::fmt;
+
+use crate::codec::{Endianness, IntegerCodec, deserialize_int_from_slice};
+use crate::error::DecodeError;
+use crate::float::serialize_float_to_string;
+use crate::varint::{serialize_to_varint, deserialize_from_varint};
+
+// Every value starts with a one-byte tag that says what follows, so a reader can decode a file
+// without being told its type. Numbers are stored big-endian at their natural width; strings and
+// byte blobs are prefixed with their length as a varint.
+//
+//   tag | type    | payload
+//   ----+---------+-------------------------------
+//   00  | bool    | 1 byte, 0 or 1
+//   01  | u8      | 1 byte
+//   02  | u16     | 2 bytes
+//   03  | u32     | 4 bytes
+//   04  | u64     | 8 bytes
+//   05  | u128    | 16 bytes
+//   11  | i8      | 1 byte
+//   12  | i16     | 2 bytes
+//   13  | i32     | 4 bytes
+//   14  | i64     | 8 bytes
+//   15  | i128    | 16 bytes
+//   20  | f32     | 4 bytes, IEEE-754 bits
+//   21  | f64     | 8 bytes, IEEE-754 bits
+//   30  | string  | varint length, UTF-8 bytes
+//   31  | bytes   | varint length, raw bytes
+
+const TAG_BOOL: u8 = 0x00;
+const TAG_U8: u8 = 0x01;
+const TAG_U16: u8 = 0x02;
+const TAG_U32: u8 = 0x03;
+const TAG_U64: u8 = 0x04;
+const TAG_U128: u8 = 0x05;
+const TAG_I8: u8 = 0x11;
+const TAG_I16: u8 = 0x12;
+const TAG_I32: u8 = 0x13;
+const TAG_I64: u8 = 0x14;
+const TAG_I128: u8 = 0x15;
+const TAG_F32: u8 = 0x20;
+const TAG_F64: u8 = 0x21;
+const TAG_STRING: u8 = 0x30;
+const TAG_BYTES: u8 = 0x31;
+
+/// A value that carries its own type in the serialized form
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+impl Value {
+    /// Name of the type, as printed by `inspect`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "bool",
+            Value::U8(_) => "u8",
+            Value::U16(_) => "u16",
+            Value::U32(_) => "u32",
+            Value::U64(_) => "u64",
+            Value::U128(_) => "u128",
+            Value::I8(_) => "i8",
+            Value::I16(_) => "i16",
+            Value::I32(_) => "i32",
+            Value::I64(_) => "i64",
+            Value::I128(_) => "i128",
+            Value::F32(_) => "f32",
+            Value::F64(_) => "f64",
+            Value::String(_) => "string",
+            Value::Bytes(_) => "bytes",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::U8(v) => write!(f, "{}", v),
+            Value::U16(v) => write!(f, "{}", v),
+            Value::U32(v) => write!(f, "{}", v),
+            Value::U64(v) => write!(f, "{}", v),
+            Value::U128(v) => write!(f, "{}", v),
+            Value::I8(v) => write!(f, "{}", v),
+            Value::I16(v) => write!(f, "{}", v),
+            Value::I32(v) => write!(f, "{}", v),
+            Value::I64(v) => write!(f, "{}", v),
+            Value::I128(v) => write!(f, "{}", v),
+            Value::F32(v) => write!(f, "{}", serialize_float_to_string(*v)),
+            Value::F64(v) => write!(f, "{}", serialize_float_to_string(*v)),
+            Value::String(v) => write!(f, "{:?}", v),
+            Value::Bytes(v) => {
+                write!(f, "[")?;
+                for (i, byte) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// Appends the tagged encoding of `value` to `out`
+pub fn serialize_tagged_into(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Bool(v) => {
+            out.push(TAG_BOOL);
+            out.push(*v as u8);
+        }
+        Value::U8(v) => push_int(out, TAG_U8, *v),
+        Value::U16(v) => push_int(out, TAG_U16, *v),
+        Value::U32(v) => push_int(out, TAG_U32, *v),
+        Value::U64(v) => push_int(out, TAG_U64, *v),
+        Value::U128(v) => push_int(out, TAG_U128, *v),
+        Value::I8(v) => push_int(out, TAG_I8, *v),
+        Value::I16(v) => push_int(out, TAG_I16, *v),
+        Value::I32(v) => push_int(out, TAG_I32, *v),
+        Value::I64(v) => push_int(out, TAG_I64, *v),
+        Value::I128(v) => push_int(out, TAG_I128, *v),
+        Value::F32(v) => push_int(out, TAG_F32, v.to_bits()),
+        Value::F64(v) => push_int(out, TAG_F64, v.to_bits()),
+        Value::String(v) => push_blob(out, TAG_STRING, v.as_bytes()),
+        Value::Bytes(v) => push_blob(out, TAG_BYTES, v),
+    }
+}
+
+/// Serializes a single value with its type tag
+pub fn serialize_tagged(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    serialize_tagged_into(value, &mut out);
+    out
+}
+
+/// Serializes a sequence of tagged values back to back
+pub fn serialize_all_tagged(values: &[Value]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for value in values {
+        serialize_tagged_into(value, &mut out);
+    }
+    out
+}
+
+fn push_int<T: IntegerCodec>(out: &mut Vec<u8>, tag: u8, value: T) {
+    out.push(tag);
+    out.extend_from_slice(value.to_bytes(Endianness::Big).as_ref());
+}
+
+fn push_blob(out: &mut Vec<u8>, tag: u8, bytes: &[u8]) {
+    out.push(tag);
+    out.extend_from_slice(&serialize_to_varint(bytes.len() as u64));
+    out.extend_from_slice(bytes);
+}
+
+/// Deserializes the tagged value at the start of `bytes`, returning it and its encoded length
+pub fn deserialize_tagged(bytes: &[u8]) -> Result<(Value, usize), DecodeError> {
+    let (&tag, payload) = bytes.split_first().ok_or(DecodeError::Truncated)?;
+    let (value, payload_len) = match tag {
+        TAG_BOOL => match payload.first() {
+            Some(0) => (Value::Bool(false), 1),
+            Some(1) => (Value::Bool(true), 1),
+            Some(&other) => return Err(DecodeError::InvalidBool(other)),
+            None => return Err(DecodeError::Truncated),
+        },
+        TAG_U8 => read_int(payload, Value::U8)?,
+        TAG_U16 => read_int(payload, Value::U16)?,
+        TAG_U32 => read_int(payload, Value::U32)?,
+        TAG_U64 => read_int(payload, Value::U64)?,
+        TAG_U128 => read_int(payload, Value::U128)?,
+        TAG_I8 => read_int(payload, Value::I8)?,
+        TAG_I16 => read_int(payload, Value::I16)?,
+        TAG_I32 => read_int(payload, Value::I32)?,
+        TAG_I64 => read_int(payload, Value::I64)?,
+        TAG_I128 => read_int(payload, Value::I128)?,
+        TAG_F32 => read_int(payload, |bits| Value::F32(f32::from_bits(bits)))?,
+        TAG_F64 => read_int(payload, |bits| Value::F64(f64::from_bits(bits)))?,
+        TAG_STRING => {
+            let (blob, len) = read_blob(payload)?;
+            let string = String::from_utf8(blob.to_vec()).map_err(|_| DecodeError::InvalidUtf8)?;
+            (Value::String(string), len)
+        }
+        TAG_BYTES => {
+            let (blob, len) = read_blob(payload)?;
+            (Value::Bytes(blob.to_vec()), len)
+        }
+        other => return Err(DecodeError::UnknownTag(other)),
+    };
+    Ok((value, 1 + payload_len))
+}
+
+/// Deserializes every tagged value in `bytes`; the input must end exactly after the last one
+pub fn deserialize_all_tagged(bytes: &[u8]) -> Result<Vec<Value>, DecodeError> {
+    let mut values = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let (value, len) = deserialize_tagged(&bytes[offset..])?;
+        values.push(value);
+        offset += len;
+    }
+    Ok(values)
+}
+
+fn read_int<T: IntegerCodec>(payload: &[u8], wrap: impl Fn(T) -> Value) -> Result<(Value, usize), DecodeError> {
+    let bytes = payload.get(..T::WIDTH).ok_or(DecodeError::Truncated)?;
+    let value = deserialize_int_from_slice::<T>(bytes, Endianness::Big)?;
+    Ok((wrap(value), T::WIDTH))
+}
+
+fn read_blob(payload: &[u8]) -> Result<(&[u8], usize), DecodeError> {
+    let (len, prefix_len) = deserialize_from_varint(payload)?;
+    let len = usize::try_from(len).map_err(|_| DecodeError::Overflow)?;
+    let end = prefix_len.checked_add(len).ok_or(DecodeError::Overflow)?;
+    let blob = payload.get(prefix_len..end).ok_or(DecodeError::Truncated)?;
+    Ok((blob, end))
+}
+
+/// Decodes every value in a tagged file and pretty-prints it, one `offset: type value` line each
+pub fn inspect(bytes: &[u8]) -> Result<String, DecodeError> {
+    let mut out = String::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let (value, len) = deserialize_tagged(&bytes[offset..])?;
+        out.push_str(&format!("{:08x}: {} {}\n", offset, value.type_name(), value));
+        offset += len;
+    }
+    Ok(out)
+}
@@ -0,0 +1,158 @@
+use crate::codec::{Endianness, IntegerCodec, deserialize_int_from_slice, deserialize_int_from_string};
+use crate::error::DecodeError;
+
+/// The human-readable encodings an integer can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    /// Plain decimal digits, e.g. `33` or `-2`
+    Decimal,
+    /// The big-endian bytes as lowercase hex, two digits per byte, e.g. `00000021` for a u32.
+    /// Signed values show their two's complement bytes (`ff` for -1i8).
+    Hex,
+    /// The big-endian bytes in URL-safe base64 without padding (RFC 4648, section 5), so the
+    /// text can go into a URL or file name as is
+    Base64,
+    /// A JSON number. Written as plain decimal; read back from any JSON number that denotes an
+    /// integer, such as `1e3` or `33.0`. Beware that many JSON readers store numbers as f64 and
+    /// lose precision above 2^53.
+    Json,
+}
+
+/// All text formats, handy for tests and tools that want to try each one
+pub const TEXT_FORMATS: [TextFormat; 4] = [TextFormat::Decimal, TextFormat::Hex, TextFormat::Base64, TextFormat::Json];
+
+/// Serializes an integer into text using the given format
+pub fn serialize_int_to_text<T: IntegerCodec>(data: T, format: TextFormat) -> String {
+    match format {
+        TextFormat::Decimal | TextFormat::Json => data.to_string(),
+        TextFormat::Hex => data.to_bytes(Endianness::Big).as_ref().iter().map(|b| format!("{:02x}", b)).collect(),
+        TextFormat::Base64 => encode_base64(data.to_bytes(Endianness::Big).as_ref()),
+    }
+}
+
+/// Parses text produced by `serialize_int_to_text` with the same format (surrounding whitespace
+/// is ignored)
+pub fn deserialize_int_from_text<T: IntegerCodec>(text: &str, format: TextFormat) -> Result<T, DecodeError> {
+    let text = text.trim();
+    match format {
+        TextFormat::Decimal => deserialize_int_from_string(text),
+        TextFormat::Hex => deserialize_int_from_slice(&decode_hex(text)?, Endianness::Big),
+        TextFormat::Base64 => deserialize_int_from_slice(&decode_base64(text)?, Endianness::Big),
+        TextFormat::Json => deserialize_int_from_json(text),
+    }
+}
+
+fn decode_hex(text: &str) -> Result<Vec<u8>, DecodeError> {
+    let not_hex = || DecodeError::NotANumber(text.to_string());
+    // from_str_radix would also accept a sign, so check the digits ourselves
+    if !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(not_hex());
+    }
+    if !text.len().is_multiple_of(2) {
+        return Err(DecodeError::Truncated);
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(|_| not_hex()))
+        .collect()
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() * 4).div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        // n bytes carry 8n bits, which need n + 1 six-bit symbols
+        for i in 0..=chunk.len() {
+            out.push(BASE64_ALPHABET[((bits >> (18 - 6 * i)) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, DecodeError> {
+    let not_base64 = || DecodeError::NotANumber(text.to_string());
+    let symbols = text
+        .bytes()
+        .map(|c| BASE64_ALPHABET.iter().position(|&a| a == c).map(|v| v as u32).ok_or_else(not_base64))
+        .collect::<Result<Vec<u32>, DecodeError>>()?;
+
+    let mut out = Vec::with_capacity(symbols.len() * 3 / 4);
+    for chunk in symbols.chunks(4) {
+        // A single leftover symbol only holds 6 bits, not enough for a byte
+        if chunk.len() == 1 {
+            return Err(DecodeError::Truncated);
+        }
+        let bits = chunk.iter().enumerate().fold(0u32, |acc, (i, &v)| acc | (v << (18 - 6 * i)));
+        let len = chunk.len() - 1;
+        // Bits past the last full byte must be zero, otherwise two texts would decode the same
+        if bits & (0xff_ffff >> (8 * len)) != 0 {
+            return Err(not_base64());
+        }
+        out.extend_from_slice(&bits.to_be_bytes()[1..=len]);
+    }
+    Ok(out)
+}
+
+/// Turns any JSON number that denotes an integer into that integer, e.g. `-1.5e1` into -15
+fn deserialize_int_from_json<T: IntegerCodec>(text: &str) -> Result<T, DecodeError> {
+    let not_json = || DecodeError::NotANumber(text.to_string());
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+    // JSON grammar: -?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?
+    let (negative, rest) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (mantissa, exponent) = match rest.find(['e', 'E']) {
+        Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+        None => (rest, None),
+    };
+    let (int_part, frac_part) = match mantissa.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, frac_part),
+        None => (mantissa, "0"),
+    };
+    if !is_digits(int_part) || !is_digits(frac_part) || (int_part.len() > 1 && int_part.starts_with('0')) {
+        return Err(not_json());
+    }
+    let exponent: i64 = match exponent {
+        Some(e) => {
+            let digits = e.strip_prefix(['+', '-']).unwrap_or(e);
+            if !is_digits(digits) {
+                return Err(not_json());
+            }
+            // Exponents this large can only mean zero or overflow; clamp to keep the math simple
+            let magnitude = digits.parse::<i64>().unwrap_or(i64::MAX / 2).min(i64::MAX / 2);
+            if e.starts_with('-') { -magnitude } else { magnitude }
+        }
+        None => 0,
+    };
+
+    // value = digits * 10^(exponent - number of fraction digits)
+    let mut digits: String = [int_part, frac_part].concat();
+    let mut scale = exponent - frac_part.len() as i64;
+    digits = digits.trim_start_matches('0').to_string();
+    if digits.is_empty() {
+        return deserialize_int_from_string("0");
+    }
+    while scale < 0 && digits.ends_with('0') {
+        digits.pop();
+        scale += 1;
+    }
+    if scale < 0 {
+        // Non-zero digits after the decimal point: not an integer
+        return Err(not_json());
+    }
+    // No integer type has more than 39 decimal digits
+    if digits.len() as i64 + scale > 40 {
+        return Err(DecodeError::Overflow);
+    }
+    digits.extend(std::iter::repeat_n('0', scale as usize));
+    if negative {
+        digits.insert(0, '-');
+    }
+    deserialize_int_from_string(&digits)
+}
@@ -0,0 +1,70 @@
+use crate::error::DecodeError;
+
+// Unsigned LEB128: 7 bits of payload per byte, least significant group first. The high bit of
+// every byte says whether another byte follows. Small values (like 33) take a single byte.
+
+/// Maximum number of bytes a `u64` can take as a varint (ceil(64 / 7))
+pub const MAX_VARINT_LEN: usize = 10;
+
+/// Serializes an unsigned integer into LEB128 varint bytes
+pub fn serialize_to_varint(data: u64) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(MAX_VARINT_LEN);
+    let mut value = data;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Deserializes a LEB128 varint from the start of `bytes`.
+///
+/// Returns the value and the number of bytes it occupied, so callers can keep reading after it.
+pub fn deserialize_from_varint(bytes: &[u8]) -> Result<(u64, usize), DecodeError> {
+    let mut value: u64 = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        if i == MAX_VARINT_LEN {
+            return Err(DecodeError::Overflow);
+        }
+        let payload = (byte & 0x7f) as u64;
+        // The 10th byte only has room for the single remaining bit of a u64
+        if i == MAX_VARINT_LEN - 1 && payload > 1 {
+            return Err(DecodeError::Overflow);
+        }
+        value |= payload << (7 * i);
+        if byte & 0x80 == 0 {
+            // A trailing zero group means a shorter encoding of the same value exists
+            if i > 0 && payload == 0 {
+                return Err(DecodeError::Overlong);
+            }
+            return Ok((value, i + 1));
+        }
+    }
+    Err(DecodeError::Truncated)
+}
+
+/// Maps signed integers onto unsigned ones so that small magnitudes stay small:
+/// 0 -> 0, -1 -> 1, 1 -> 2, -2 -> 3, ...
+pub fn zigzag_encode(data: i64) -> u64 {
+    ((data << 1) ^ (data >> 63)) as u64
+}
+
+/// Inverse of `zigzag_encode`
+pub fn zigzag_decode(data: u64) -> i64 {
+    ((data >> 1) as i64) ^ -((data & 1) as i64)
+}
+
+/// Serializes a signed integer as a zigzag-mapped LEB128 varint
+pub fn serialize_to_zigzag(data: i64) -> Vec<u8> {
+    serialize_to_varint(zigzag_encode(data))
+}
+
+/// Deserializes a zigzag varint from the start of `bytes`, returning the value and its length
+pub fn deserialize_from_zigzag(bytes: &[u8]) -> Result<(i64, usize), DecodeError> {
+    let (value, len) = deserialize_from_varint(bytes)?;
+    Ok((zigzag_decode(value), len))
+}
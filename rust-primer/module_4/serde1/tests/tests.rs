@@ -20,3 +20,649 @@ fn check_deserialize_from_bytes() {
     let integer_deser = deserialize_from_bytes(integer.to_be_bytes());
     assert_eq!(integer_deser, integer);
 }
+
+mod codec {
+    use solution::{Endianness, IntegerCodec,
+                   serialize_int_to_string, deserialize_int_from_string,
+                   serialize_int_to_bytes, deserialize_int_from_bytes};
+
+    const ORDERS: [Endianness; 3] = [Endianness::Big, Endianness::Little, Endianness::Native];
+
+    fn round_trip<T: IntegerCodec + PartialEq + std::fmt::Debug>(values: &[T]) {
+        for &value in values {
+            for order in ORDERS {
+                let bytes = serialize_int_to_bytes(value, order);
+                assert_eq!(bytes.as_ref().len(), T::WIDTH);
+                assert_eq!(deserialize_int_from_bytes::<T>(bytes, order), value);
+            }
+            let string = serialize_int_to_string(value);
+            assert_eq!(deserialize_int_from_string::<T>(&string).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn check_round_trip_unsigned() {
+        round_trip::<u8>(&[0, 1, 33, u8::MAX]);
+        round_trip::<u16>(&[0, 1, 33, 0x1234, u16::MAX]);
+        round_trip::<u32>(&[0, 1, 33, 0x1234_5678, u32::MAX]);
+        round_trip::<u64>(&[0, 1, 33, 0x0102_0304_0506_0708, u64::MAX]);
+        round_trip::<u128>(&[0, 1, 33, 0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10, u128::MAX]);
+    }
+
+    #[test]
+    fn check_round_trip_signed() {
+        round_trip::<i8>(&[0, -1, 33, i8::MIN, i8::MAX]);
+        round_trip::<i16>(&[0, -1, 33, i16::MIN, i16::MAX]);
+        round_trip::<i32>(&[0, -1, 33, i32::MIN, i32::MAX]);
+        round_trip::<i64>(&[0, -1, 33, i64::MIN, i64::MAX]);
+        round_trip::<i128>(&[0, -1, 33, i128::MIN, i128::MAX]);
+    }
+
+    #[test]
+    fn check_byte_order() {
+        assert_eq!(serialize_int_to_bytes(0x1234u16, Endianness::Big), [0x12, 0x34]);
+        assert_eq!(serialize_int_to_bytes(0x1234u16, Endianness::Little), [0x34, 0x12]);
+        assert_eq!(serialize_int_to_bytes(0x1234u16, Endianness::Native), 0x1234u16.to_ne_bytes());
+        assert_eq!(serialize_int_to_bytes(-2i32, Endianness::Big), [0xff, 0xff, 0xff, 0xfe]);
+        assert_eq!(serialize_int_to_bytes(-2i32, Endianness::Little), [0xfe, 0xff, 0xff, 0xff]);
+        assert_eq!(deserialize_int_from_bytes::<u64>([0, 0, 0, 0, 0, 0, 0, 33], Endianness::Big), 33);
+        assert_eq!(deserialize_int_from_bytes::<u64>([33, 0, 0, 0, 0, 0, 0, 0], Endianness::Little), 33);
+    }
+
+    #[test]
+    fn check_string_rejects_out_of_range() {
+        assert!(deserialize_int_from_string::<u8>("256").is_err());
+        assert!(deserialize_int_from_string::<u32>("-1").is_err());
+        assert_eq!(deserialize_int_from_string::<i8>("-128").unwrap(), i8::MIN);
+    }
+}
+
+mod slice {
+    use solution::{DecodeError, Endianness,
+                   deserialize_from_slice, deserialize_from_string, deserialize_int_from_slice,
+                   serialize_to_bytes, serialize_to_string};
+
+    #[test]
+    fn check_deserialize_from_slice() {
+        let bytes = serialize_to_bytes(33);
+        assert_eq!(deserialize_from_slice(&bytes), Ok(33));
+        assert_eq!(deserialize_int_from_slice::<u16>(&[0x01, 0x02], Endianness::Little), Ok(0x0201));
+    }
+
+    #[test]
+    fn check_deserialize_from_slice_errors() {
+        assert_eq!(deserialize_from_slice(&[]), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_from_slice(&[0, 0, 33]), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_from_slice(&[0, 0, 0, 33, 10]), Err(DecodeError::TrailingBytes(1)));
+        assert_eq!(deserialize_int_from_slice::<u8>(&[1, 2, 3], Endianness::Big), Err(DecodeError::TrailingBytes(2)));
+    }
+
+    #[test]
+    fn check_deserialize_from_string() {
+        assert_eq!(deserialize_from_string(&serialize_to_string(33)), Ok(33));
+        assert_eq!(deserialize_from_string("33\n"), Ok(33));
+        assert_eq!(deserialize_from_string(""), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_from_string("4294967296"), Err(DecodeError::Overflow));
+        assert_eq!(deserialize_from_string("3x3"), Err(DecodeError::NotANumber("3x3".to_string())));
+        assert_eq!(deserialize_from_string("-1"), Err(DecodeError::NotANumber("-1".to_string())));
+    }
+}
+
+mod varint {
+    use solution::{DecodeError,
+                   serialize_to_varint, deserialize_from_varint,
+                   serialize_to_zigzag, deserialize_from_zigzag,
+                   zigzag_encode, zigzag_decode, serialize_to_bytes};
+
+    #[test]
+    fn check_varint_known_encodings() {
+        assert_eq!(serialize_to_varint(0), [0x00]);
+        assert_eq!(serialize_to_varint(33), [0x21]);
+        assert_eq!(serialize_to_varint(127), [0x7f]);
+        assert_eq!(serialize_to_varint(128), [0x80, 0x01]);
+        assert_eq!(serialize_to_varint(300), [0xac, 0x02]);
+        assert_eq!(serialize_to_varint(u64::MAX).len(), 10);
+    }
+
+    #[test]
+    fn check_varint_smaller_than_fixed_width() {
+        assert!(serialize_to_varint(33).len() < serialize_to_bytes(33).len());
+    }
+
+    #[test]
+    fn check_varint_round_trip() {
+        for value in [0, 1, 33, 127, 128, 300, 16383, 16384, u32::MAX as u64, u64::MAX - 1, u64::MAX] {
+            let bytes = serialize_to_varint(value);
+            assert_eq!(deserialize_from_varint(&bytes), Ok((value, bytes.len())));
+        }
+    }
+
+    #[test]
+    fn check_varint_reports_consumed_length() {
+        let mut bytes = serialize_to_varint(300);
+        bytes.extend_from_slice(&[0xff, 0xff]);
+        assert_eq!(deserialize_from_varint(&bytes), Ok((300, 2)));
+    }
+
+    #[test]
+    fn check_varint_errors() {
+        assert_eq!(deserialize_from_varint(&[]), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_from_varint(&[0x80]), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_from_varint(&[0xac]), Err(DecodeError::Truncated));
+        // 0 and 1 padded with an extra zero group
+        assert_eq!(deserialize_from_varint(&[0x80, 0x00]), Err(DecodeError::Overlong));
+        assert_eq!(deserialize_from_varint(&[0x81, 0x80, 0x00]), Err(DecodeError::Overlong));
+        // 2^64 does not fit
+        let mut too_big = vec![0x80; 9];
+        too_big.push(0x02);
+        assert_eq!(deserialize_from_varint(&too_big), Err(DecodeError::Overflow));
+        assert_eq!(deserialize_from_varint(&[0xff; 11]), Err(DecodeError::Overflow));
+    }
+
+    #[test]
+    fn check_zigzag_mapping() {
+        assert_eq!(zigzag_encode(0), 0);
+        assert_eq!(zigzag_encode(-1), 1);
+        assert_eq!(zigzag_encode(1), 2);
+        assert_eq!(zigzag_encode(-2), 3);
+        assert_eq!(zigzag_encode(i64::MAX), u64::MAX - 1);
+        assert_eq!(zigzag_encode(i64::MIN), u64::MAX);
+        for value in [0, 1, -1, 33, -33, i64::MIN, i64::MAX] {
+            assert_eq!(zigzag_decode(zigzag_encode(value)), value);
+        }
+    }
+
+    #[test]
+    fn check_zigzag_round_trip() {
+        assert_eq!(serialize_to_zigzag(-1), [0x01]);
+        for value in [0, 1, -1, 33, -33, 1 << 40, -(1 << 40), i64::MIN, i64::MAX] {
+            let bytes = serialize_to_zigzag(value);
+            assert_eq!(deserialize_from_zigzag(&bytes), Ok((value, bytes.len())));
+        }
+        assert_eq!(deserialize_from_zigzag(&[0x81]), Err(DecodeError::Truncated));
+    }
+}
+
+mod atomic {
+    use solution::{AtomicWriteStep, write_atomically, write_atomically_with_hook};
+    use std::fs;
+    use std::io::Error;
+    use std::path::PathBuf;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("serde1_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn check_write_atomically_replaces_contents() {
+        let dir = scratch_dir("atomic_replace");
+        let path = dir.join("test.bytes");
+        write_atomically(&path, b"old contents").unwrap();
+        write_atomically(&path, &33u32.to_be_bytes()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), 33u32.to_be_bytes());
+        // Only the target is left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_crash_before_rename_keeps_old_file() {
+        for crash_at in [AtomicWriteStep::Written, AtomicWriteStep::Synced] {
+            let dir = scratch_dir("atomic_crash");
+            let path = dir.join("test.bytes");
+            write_atomically(&path, b"old contents").unwrap();
+
+            let result = write_atomically_with_hook(&path, b"new contents, much longer than before", |step| {
+                if step == crash_at {
+                    Err(Error::other("simulated crash"))
+                } else {
+                    Ok(())
+                }
+            });
+            assert!(result.is_err());
+            assert_eq!(fs::read(&path).unwrap(), b"old contents");
+            assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn check_crash_after_rename_has_new_file() {
+        let dir = scratch_dir("atomic_crash_after");
+        let path = dir.join("test.bytes");
+        write_atomically(&path, b"old contents").unwrap();
+
+        let mut steps = Vec::new();
+        let result = write_atomically_with_hook(&path, b"new contents", |step| {
+            steps.push(step);
+            if step == AtomicWriteStep::Renamed {
+                Err(Error::other("simulated crash"))
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_err());
+        assert_eq!(steps, [AtomicWriteStep::Written, AtomicWriteStep::Synced, AtomicWriteStep::Renamed]);
+        assert_eq!(fs::read(&path).unwrap(), b"new contents");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_concurrent_writes_to_one_target() {
+        let dir = scratch_dir("atomic_concurrent");
+        let path = dir.join("test.bytes");
+        let contents: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 64 * 1024]).collect();
+        std::thread::scope(|scope| {
+            for bytes in &contents {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        write_atomically(path, bytes).unwrap();
+                    }
+                });
+            }
+        });
+        // Whichever write came last, it is there in full
+        assert!(contents.contains(&fs::read(&path).unwrap()));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+mod frame {
+    use solution::{DecodeError, FRAME_VERSION, crc32,
+                   serialize_to_frame, deserialize_from_frame,
+                   serialize_to_framed_bytes, deserialize_from_framed_bytes};
+
+    #[test]
+    fn check_crc32_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"The quick brown fox jumps over the lazy dog"), 0x414F_A339);
+    }
+
+    #[test]
+    fn check_frame_round_trip() {
+        let bytes = serialize_to_framed_bytes(33);
+        assert_eq!(bytes.len(), 4 + 1 + 4 + 4 + 4);
+        assert_eq!(deserialize_from_framed_bytes(&bytes), Ok(33));
+        assert_eq!(deserialize_from_frame(&serialize_to_frame(b"").unwrap()), Ok(&b""[..]));
+        assert_eq!(deserialize_from_frame(&serialize_to_frame(b"hello").unwrap()), Ok(&b"hello"[..]));
+    }
+
+    #[test]
+    fn check_frame_detects_every_bit_flip() {
+        let bytes = serialize_to_framed_bytes(2147483647);
+        for i in 0..bytes.len() * 8 {
+            let mut corrupted = bytes.clone();
+            corrupted[i / 8] ^= 1 << (i % 8);
+            assert!(deserialize_from_framed_bytes(&corrupted).is_err(), "bit {} flip went unnoticed", i);
+        }
+    }
+
+    #[test]
+    fn check_frame_detects_truncation() {
+        let bytes = serialize_to_framed_bytes(33);
+        for len in 0..bytes.len() {
+            let result = deserialize_from_framed_bytes(&bytes[..len]);
+            assert_eq!(result, Err(DecodeError::Truncated), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn check_frame_names_failed_check() {
+        let bytes = serialize_to_framed_bytes(33);
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(deserialize_from_framed_bytes(&bad_magic), Err(DecodeError::BadMagic));
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = FRAME_VERSION + 1;
+        assert_eq!(deserialize_from_framed_bytes(&bad_version), Err(DecodeError::UnsupportedVersion(FRAME_VERSION + 1)));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(deserialize_from_framed_bytes(&trailing), Err(DecodeError::TrailingBytes(1)));
+
+        let mut bad_payload = bytes.clone();
+        bad_payload[9] ^= 0xff;
+        assert!(matches!(deserialize_from_framed_bytes(&bad_payload), Err(DecodeError::ChecksumMismatch { .. })));
+
+        // A frame holding the wrong payload size for a u32 is valid as a frame but not as a u32
+        assert_eq!(deserialize_from_framed_bytes(&serialize_to_frame(&[1, 2]).unwrap()), Err(DecodeError::Truncated));
+    }
+}
+
+mod tagged {
+    use solution::{DecodeError, Value, inspect,
+                   serialize_tagged, deserialize_tagged, serialize_all_tagged, deserialize_all_tagged};
+
+    fn every_kind() -> Vec<Value> {
+        vec![
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::U8(u8::MAX),
+            Value::U16(0x1234),
+            Value::U32(33),
+            Value::U64(u64::MAX),
+            Value::U128(u128::MAX - 1),
+            Value::I8(i8::MIN),
+            Value::I16(-2),
+            Value::I32(-33),
+            Value::I64(i64::MIN),
+            Value::I128(i128::MAX),
+            Value::F32(1.5),
+            Value::F64(-0.1),
+            Value::String("héllo".to_string()),
+            Value::String(String::new()),
+            Value::Bytes(vec![0xde, 0xad, 0xbe, 0xef]),
+        ]
+    }
+
+    #[test]
+    fn check_tagged_round_trip() {
+        for value in every_kind() {
+            let bytes = serialize_tagged(&value);
+            assert_eq!(deserialize_tagged(&bytes), Ok((value, bytes.len())));
+        }
+        let values = every_kind();
+        assert_eq!(deserialize_all_tagged(&serialize_all_tagged(&values)), Ok(values));
+    }
+
+    #[test]
+    fn check_tagged_layout() {
+        assert_eq!(serialize_tagged(&Value::U32(33)), [0x03, 0, 0, 0, 33]);
+        assert_eq!(serialize_tagged(&Value::Bool(true)), [0x00, 1]);
+        assert_eq!(serialize_tagged(&Value::String("hi".to_string())), [0x30, 2, b'h', b'i']);
+    }
+
+    #[test]
+    fn check_tagged_errors() {
+        assert_eq!(deserialize_tagged(&[]), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_tagged(&[0x03, 0, 0]), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_tagged(&[0x30, 5, b'h', b'i']), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_tagged(&[0xff, 0]), Err(DecodeError::UnknownTag(0xff)));
+        assert_eq!(deserialize_tagged(&[0x00, 2]), Err(DecodeError::InvalidBool(2)));
+        assert_eq!(deserialize_tagged(&[0x30, 2, 0xc3, 0x28]), Err(DecodeError::InvalidUtf8));
+        let mut cut = serialize_all_tagged(&every_kind());
+        cut.pop();
+        assert_eq!(deserialize_all_tagged(&cut), Err(DecodeError::Truncated));
+    }
+
+    #[test]
+    fn check_inspect() {
+        let bytes = serialize_all_tagged(&[
+            Value::U32(33),
+            Value::I16(-2),
+            Value::String("mercury".to_string()),
+            Value::Bytes(vec![1, 255]),
+            Value::F64(0.5),
+        ]);
+        let expected = "\
+00000000: u32 33
+00000005: i16 -2
+00000008: string \"mercury\"
+00000011: bytes [01 ff]
+00000015: f64 0.5
+";
+        assert_eq!(inspect(&bytes).unwrap(), expected);
+        assert_eq!(inspect(&[0x42]), Err(DecodeError::UnknownTag(0x42)));
+    }
+}
+
+mod float {
+    use solution::{DecodeError, Endianness,
+                   serialize_float_to_string, deserialize_float_from_string,
+                   serialize_float_to_bytes, deserialize_float_from_bytes, deserialize_float_from_slice};
+
+    const F64_VALUES: [f64; 12] = [
+        0.0, 1.0, 0.1, -2.5, 1.0 / 3.0, std::f64::consts::PI, 1e300, 1e-300,
+        f64::MIN_POSITIVE, f64::MAX, f64::EPSILON, 5e-324,
+    ];
+
+    #[test]
+    fn check_float_text_round_trip() {
+        for value in F64_VALUES {
+            let text = serialize_float_to_string(value);
+            let back: f64 = deserialize_float_from_string(&text).unwrap();
+            assert_eq!(back.to_bits(), value.to_bits(), "{} -> {}", value, text);
+        }
+        for value in [0.1f32, 1.0 / 3.0, f32::MAX, f32::MIN_POSITIVE, -7.25] {
+            let text = serialize_float_to_string(value);
+            let back: f32 = deserialize_float_from_string(&text).unwrap();
+            assert_eq!(back.to_bits(), value.to_bits(), "{} -> {}", value, text);
+        }
+    }
+
+    #[test]
+    fn check_float_text_is_shortest() {
+        assert_eq!(serialize_float_to_string(0.1f64), "0.1");
+        assert_eq!(serialize_float_to_string(1.0f64), "1");
+        assert_eq!(serialize_float_to_string(1e300f64), "1e300");
+        assert_eq!(serialize_float_to_string(1.5e-7f64), "1.5e-7");
+        assert_eq!(serialize_float_to_string(0.1f32), "0.1");
+        // One digit fewer would already be a different f64
+        let third = serialize_float_to_string(1.0f64 / 3.0);
+        let shorter: f64 = third[..third.len() - 1].parse().unwrap();
+        assert_ne!(shorter, 1.0 / 3.0);
+    }
+
+    #[test]
+    fn check_float_special_values_text() {
+        for value in [f64::INFINITY, f64::NEG_INFINITY, -0.0] {
+            let back: f64 = deserialize_float_from_string(&serialize_float_to_string(value)).unwrap();
+            assert_eq!(back.to_bits(), value.to_bits());
+        }
+        let nan: f64 = deserialize_float_from_string(&serialize_float_to_string(f64::NAN)).unwrap();
+        assert!(nan.is_nan());
+        assert_eq!(deserialize_float_from_string::<f64>(""), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_float_from_string::<f64>("1.2.3"), Err(DecodeError::NotANumber("1.2.3".to_string())));
+    }
+
+    #[test]
+    fn check_float_binary_preserves_bits() {
+        let payload_nan = f64::from_bits(0x7ff8_0000_dead_beef);
+        let signaling_nan = f32::from_bits(0x7f80_0001);
+        for order in [Endianness::Big, Endianness::Little, Endianness::Native] {
+            for value in F64_VALUES.iter().copied().chain([payload_nan, -0.0, f64::INFINITY, f64::NEG_INFINITY]) {
+                let bytes = serialize_float_to_bytes(value, order);
+                assert_eq!(bytes.len(), 8);
+                assert_eq!(deserialize_float_from_bytes::<f64>(bytes, order).to_bits(), value.to_bits());
+                assert_eq!(deserialize_float_from_slice::<f64>(&bytes, order).unwrap().to_bits(), value.to_bits());
+            }
+            let bytes = serialize_float_to_bytes(signaling_nan, order);
+            assert_eq!(deserialize_float_from_bytes::<f32>(bytes, order).to_bits(), signaling_nan.to_bits());
+        }
+        assert_eq!(serialize_float_to_bytes(-0.0f32, Endianness::Big), [0x80, 0, 0, 0]);
+        assert_eq!(deserialize_float_from_slice::<f32>(&[0, 0], Endianness::Big), Err(DecodeError::Truncated));
+    }
+
+    #[test]
+    fn check_float_text_vs_binary() {
+        // Text takes anywhere from 1 to 24 bytes per f64; binary always takes 8. A fixed-precision
+        // text format is cheaper for some values but silently loses bits.
+        println!("{:>24} {:>10} {:>12} {:>14}", "value", "text size", "binary size", "{:.6} exact?");
+        let mut text_total = 0;
+        let mut lossy_count = 0;
+        for value in F64_VALUES {
+            let text = serialize_float_to_string(value);
+            let binary = serialize_float_to_bytes(value, Endianness::Big);
+            let fixed = format!("{:.6}", value);
+            let fixed_exact = fixed.parse::<f64>().unwrap().to_bits() == value.to_bits();
+            println!("{:>24} {:>10} {:>12} {:>14}", text, text.len(), binary.len(), fixed_exact);
+            text_total += text.len();
+            if !fixed_exact {
+                lossy_count += 1;
+            }
+        }
+        println!("total: text {} bytes, binary {} bytes", text_total, 8 * F64_VALUES.len());
+        assert!(serialize_float_to_string(1.0f64).len() < 8);
+        assert!(serialize_float_to_string(std::f64::consts::PI).len() > 8);
+        assert!(lossy_count > 0);
+    }
+}
+
+mod stream {
+    use solution::{DecodeError, Decoder, Encoder, Endianness};
+    use std::fs::File;
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn check_stream_round_trip() {
+        let mut encoder = Encoder::new(Vec::new());
+        for value in [0, 33, u32::MAX] {
+            encoder.encode(value).unwrap();
+        }
+        let bytes = encoder.finish().unwrap();
+        assert_eq!(bytes, [0, 0, 0, 0, 0, 0, 0, 33, 255, 255, 255, 255]);
+
+        let values: Result<Vec<u32>, DecodeError> = Decoder::new(Cursor::new(bytes)).collect();
+        assert_eq!(values, Ok(vec![0, 33, u32::MAX]));
+        assert_eq!(Decoder::new(Cursor::new(Vec::new())).count(), 0);
+    }
+
+    #[test]
+    fn check_stream_endianness() {
+        let mut encoder = Encoder::with_endianness(Vec::new(), Endianness::Little);
+        encoder.encode(33).unwrap();
+        let bytes = encoder.finish().unwrap();
+        assert_eq!(bytes, [33, 0, 0, 0]);
+        let mut decoder = Decoder::with_endianness(Cursor::new(bytes), Endianness::Little);
+        assert_eq!(decoder.next(), Some(Ok(33)));
+        assert_eq!(decoder.next(), None);
+    }
+
+    #[test]
+    fn check_stream_truncated_tail() {
+        let bytes = vec![0, 0, 0, 33, 0, 0];
+        let mut decoder = Decoder::new(Cursor::new(bytes));
+        assert_eq!(decoder.next(), Some(Ok(33)));
+        assert_eq!(decoder.next(), Some(Err(DecodeError::Truncated)));
+        assert_eq!(decoder.next(), None);
+    }
+
+    /// Hands out one byte per read call, like a slow pipe or socket
+    struct Trickle(Vec<u8>, usize);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.get(self.1) {
+                Some(&byte) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.1 += 1;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn check_stream_short_reads() {
+        let values: Vec<u32> = Decoder::new(Trickle(vec![0, 0, 1, 0, 0, 0, 0, 33], 0))
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(values, [256, 33]);
+    }
+
+    #[test]
+    fn check_stream_million_values_through_file() {
+        let n: u32 = 1_000_000;
+        let path = std::env::temp_dir().join(format!("serde1_stream_{}.bytes", std::process::id()));
+
+        let mut encoder = Encoder::new(File::create(&path).unwrap());
+        for value in 0..n {
+            encoder.encode(value).unwrap();
+        }
+        encoder.finish().unwrap().sync_all().unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 4 * n as u64);
+
+        // Folding over the iterator never materializes the values
+        let (count, sum) = Decoder::new(File::open(&path).unwrap())
+            .fold((0u64, 0u64), |(count, sum), value| (count + 1, sum + value.unwrap() as u64));
+        assert_eq!(count, n as u64);
+        assert_eq!(sum, (n as u64) * (n as u64 - 1) / 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+mod text {
+    use solution::{DecodeError, IntegerCodec, TextFormat, TEXT_FORMATS,
+                   serialize_int_to_text, deserialize_int_from_text};
+
+    fn round_trip<T: IntegerCodec + PartialEq + std::fmt::Debug>(values: &[T]) {
+        for format in TEXT_FORMATS {
+            for &value in values {
+                let text = serialize_int_to_text(value, format);
+                assert_eq!(deserialize_int_from_text::<T>(&text, format), Ok(value), "{:?} {:?}", format, text);
+            }
+        }
+    }
+
+    #[test]
+    fn check_every_format_every_width() {
+        round_trip::<u8>(&[0, 1, 33, u8::MAX]);
+        round_trip::<u16>(&[0, 1, 33, u16::MAX]);
+        round_trip::<u32>(&[0, 1, 33, 2147483647, u32::MAX]);
+        round_trip::<u64>(&[0, 1, 33, 1 << 53, u64::MAX]);
+        round_trip::<u128>(&[0, 1, 33, u128::MAX]);
+        round_trip::<i8>(&[0, -1, 33, i8::MIN, i8::MAX]);
+        round_trip::<i16>(&[0, -1, 33, i16::MIN, i16::MAX]);
+        round_trip::<i32>(&[0, -1, 33, i32::MIN, i32::MAX]);
+        round_trip::<i64>(&[0, -1, 33, i64::MIN, i64::MAX]);
+        round_trip::<i128>(&[0, -1, 33, i128::MIN, i128::MAX]);
+    }
+
+    #[test]
+    fn check_known_encodings() {
+        assert_eq!(serialize_int_to_text(33u32, TextFormat::Decimal), "33");
+        assert_eq!(serialize_int_to_text(33u32, TextFormat::Hex), "00000021");
+        assert_eq!(serialize_int_to_text(-1i8, TextFormat::Hex), "ff");
+        assert_eq!(serialize_int_to_text(33u32, TextFormat::Base64), "AAAAIQ");
+        assert_eq!(serialize_int_to_text(u32::MAX, TextFormat::Base64), "_____w");
+        assert_eq!(serialize_int_to_text(0xfbffu16, TextFormat::Base64), "-_8");
+        assert_eq!(serialize_int_to_text(0x4d616eu32, TextFormat::Base64), "AE1hbg");
+        assert_eq!(serialize_int_to_text(-33i64, TextFormat::Json), "-33");
+    }
+
+    #[test]
+    fn check_hex_and_base64_errors() {
+        assert_eq!(deserialize_int_from_text::<u32>("ABCDEF01", TextFormat::Hex), Ok(0xabcdef01));
+        assert_eq!(deserialize_int_from_text::<u32>("000021", TextFormat::Hex), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_int_from_text::<u32>("0000021", TextFormat::Hex), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_int_from_text::<u32>("0000002100", TextFormat::Hex), Err(DecodeError::TrailingBytes(1)));
+        assert!(matches!(deserialize_int_from_text::<u32>("0000002g", TextFormat::Hex), Err(DecodeError::NotANumber(_))));
+        assert!(matches!(deserialize_int_from_text::<u32>("+0000021", TextFormat::Hex), Err(DecodeError::NotANumber(_))));
+        assert!(matches!(deserialize_int_from_text::<u32>("AAAA/Q", TextFormat::Base64), Err(DecodeError::NotANumber(_))));
+        // "AAAAIR" has a non-zero bit after the last full byte
+        assert!(matches!(deserialize_int_from_text::<u32>("AAAAIR", TextFormat::Base64), Err(DecodeError::NotANumber(_))));
+        assert_eq!(deserialize_int_from_text::<u32>("AAAAI", TextFormat::Base64), Err(DecodeError::Truncated));
+        assert_eq!(deserialize_int_from_text::<u32>("AAA", TextFormat::Base64), Err(DecodeError::Truncated));
+    }
+
+    #[test]
+    fn check_json_numbers() {
+        let json = |text| deserialize_int_from_text::<i32>(text, TextFormat::Json);
+        assert_eq!(json("33"), Ok(33));
+        assert_eq!(json("-33"), Ok(-33));
+        assert_eq!(json("0"), Ok(0));
+        assert_eq!(json("-0"), Ok(0));
+        assert_eq!(json("1e3"), Ok(1000));
+        assert_eq!(json("1E+3"), Ok(1000));
+        assert_eq!(json("33.0"), Ok(33));
+        assert_eq!(json("-1.5e1"), Ok(-15));
+        assert_eq!(json("1500e-2"), Ok(15));
+        assert_eq!(json("0.0e99999999999999999999"), Ok(0));
+        assert_eq!(deserialize_int_from_text::<u8>("0", TextFormat::Json), Ok(0));
+        assert_eq!(json("1e10"), Err(DecodeError::Overflow));
+        assert_eq!(json("1e99999999999999999999"), Err(DecodeError::Overflow));
+        for bad in ["1.5", "01", "+1", ".5", "1.", "1e", "0x10", "", " - 1"] {
+            assert!(json(bad).is_err(), "{:?} should not parse", bad);
+        }
+        assert!(deserialize_int_from_text::<u32>("-1", TextFormat::Json).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::error::KvError;
+
+// A Bloom filter answers "is this key in the set?" with either "definitely not" or "probably".
+// Every key sets `hashes` bits out of `bits`; a key whose bits are not all set was never added.
+// With n keys and a target false-positive rate p, the best choice is
+//
+//   bits   = -n ln(p) / ln(2)^2
+//   hashes = bits / n * ln(2)
+//
+// which costs about 9.6 bits per key for p = 1%. The bit positions come from two 64-bit hashes of
+// the key combined as h1 + i * h2 (Kirsch and Mitzenmacher), so each key is hashed only once.
+//
+// Serialized form (little-endian):
+//
+//   hashes   u32
+//   bits     u64
+//   words    ceil(bits / 64) * u64
+
+/// A set of keys that can answer "definitely absent" without false negatives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    words: Vec<u64>,
+    bits: u64,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// An empty filter sized for `keys` keys at the given false-positive rate (between 0 and 1)
+    pub fn with_rate(keys: usize, false_positive_rate: f64) -> Self {
+        let rate = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-(keys.max(1) as f64) * rate.ln() / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let hashes = ((bits as f64 / keys.max(1) as f64) * ln2).round().clamp(1.0, 32.0) as u32;
+        BloomFilter { words: vec![0; bits.div_ceil(64) as usize], bits, hashes }
+    }
+
+    /// Number of bits in the filter
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Number of bits each key sets
+    pub fn hashes(&self) -> u32 {
+        self.hashes
+    }
+
+    pub fn insert(&mut self, key: &str) {
+        for bit in positions(key, self.bits, self.hashes) {
+            self.words[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// `false` means `key` was definitely never inserted; `true` means it probably was
+    pub fn may_contain(&self, key: &str) -> bool {
+        positions(key, self.bits, self.hashes).all(|bit| self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Appends the serialized filter to `out`
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.hashes.to_le_bytes());
+        out.extend_from_slice(&self.bits.to_le_bytes());
+        for word in &self.words {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    /// Reads a filter written by `encode`; `bytes` must hold exactly that
+    pub fn decode(bytes: &[u8]) -> Result<Self, KvError> {
+        let corrupt = KvError::Corrupt("Bloom filter has the wrong length");
+        let fixed = bytes.get(..12).ok_or(KvError::Corrupt("Bloom filter is cut short"))?;
+        let hashes = u32::from_le_bytes(fixed[0..4].try_into().unwrap());
+        let bits = u64::from_le_bytes(fixed[4..12].try_into().unwrap());
+        if bits == 0 || hashes == 0 {
+            return Err(KvError::Corrupt("Bloom filter is empty"));
+        }
+        if bits.div_ceil(64).checked_mul(8) != Some(bytes.len() as u64 - 12) {
+            return Err(corrupt);
+        }
+        let words = bytes[12..].chunks_exact(8).map(|w| u64::from_le_bytes(w.try_into().unwrap())).collect();
+        Ok(BloomFilter { words, bits, hashes })
+    }
+}
+
+/// The `hashes` bits out of `bits` that `key` maps to
+fn positions(key: &str, bits: u64, hashes: u32) -> impl Iterator<Item = u64> {
+    let h1 = mix(fnv1a(key.as_bytes()));
+    // An odd step visits different bits for every i
+    let h2 = mix(h1 ^ 0x9e37_79b9_7f4a_7c15) | 1;
+    (0..hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bits)
+}
+
+/// 64-bit FNV-1a; stable across platforms and Rust versions, unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+/// The splitmix64 finalizer, which spreads FNV's weak high bits over the whole word
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,42 @@
+use std::fmt;
+use std::io::Error;
+
+use crate::log::MAX_KEY_LEN;
+
+/// Everything that can go wrong reading or writing a key-value log
+#[derive(Debug)]
+pub enum KvError {
+    /// The file could not be read or written
+    Io(Error),
+    /// The file does not start with the log magic bytes, so it is not a key-value log
+    BadMagic,
+    /// The log was written with a format version this code cannot read
+    UnsupportedVersion(u8),
+    /// A record in the middle of the log is inconsistent
+    Corrupt(&'static str),
+    /// A key of this many bytes is longer than a log can hold
+    KeyTooLong(usize),
+    /// A failed write could not be undone, so the map takes no more writes
+    Poisoned,
+}
+
+impl fmt::Display for KvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KvError::Io(e) => write!(f, "I/O error: {}", e),
+            KvError::BadMagic => write!(f, "not a key-value log (bad magic bytes)"),
+            KvError::UnsupportedVersion(v) => write!(f, "unsupported log version {}", v),
+            KvError::Corrupt(reason) => write!(f, "corrupt log: {}", reason),
+            KvError::KeyTooLong(len) => write!(f, "key of {} bytes is longer than the maximum of {}", len, MAX_KEY_LEN),
+            KvError::Poisoned => write!(f, "an earlier write failed and could not be undone"),
+        }
+    }
+}
+
+impl std::error::Error for KvError {}
+
+impl From<Error> for KvError {
+    fn from(e: Error) -> Self {
+        KvError::Io(e)
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Error, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::KvError;
+use crate::log::{check_key, log_header, LogReader, Record, LOG_HEADER_LEN};
+
+// A Bitcask-style store: the log on disk is the only copy of the data, and memory holds just an
+// index from every live key to the record with its latest value. A put or delete is one append to
+// the log plus an index update; a get is one seek and a 4-byte read. Opening the store replays the
+// log to rebuild the index.
+//
+// Overwritten values and deleted keys stay in the log as dead bytes. Compaction copies the live
+// records into a new segment next to the log, syncs it and renames it over the log, so a crash
+// leaves either the old log or the new one, never a mix. It runs on its own once dead bytes make
+// up more than `compaction_threshold` of a log of at least `min_compaction_len` bytes. A write
+// that triggers a compaction succeeds whether or not the compaction does; a failed compaction
+// leaves the old log in place and is tried again on the next write.
+
+/// Where a record lives in the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LogPointer {
+    offset: u64,
+    len: u64,
+}
+
+/// Settings for a `KvStore`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KvOptions {
+    /// Compact once this fraction of the log (0.0 to 1.0) is dead bytes
+    pub compaction_threshold: f64,
+    /// Never compact logs smaller than this many bytes; rewriting them would gain little
+    pub min_compaction_len: u64,
+}
+
+impl Default for KvOptions {
+    /// Compact when half of a log of at least 1 MiB is dead
+    fn default() -> Self {
+        KvOptions { compaction_threshold: 0.5, min_compaction_len: 1 << 20 }
+    }
+}
+
+/// A persistent map from strings to i32s, backed by an append-only log
+pub struct KvStore {
+    path: PathBuf,
+    file: File,
+    options: KvOptions,
+    index: HashMap<String, LogPointer>,
+    /// Where the next record goes
+    end: u64,
+    /// Bytes taken by the records the index points to; everything else after the header is dead
+    live_bytes: u64,
+    compactions: u64,
+    /// Why the last automatic compaction failed, until someone asks
+    compaction_error: Option<KvError>,
+}
+
+impl KvStore {
+    /// Opens the log at `filename`, creating an empty one if there is no file yet, and rebuilds
+    /// the index from it. An incomplete record at the end, left by a crash during a write, is
+    /// cut off; any other damage is an error and leaves the file as it is.
+    pub fn open(filename: &str) -> Result<Self, KvError> {
+        KvStore::open_with_options(filename, KvOptions::default())
+    }
+
+    /// Like `open`, with the given settings
+    pub fn open_with_options(filename: &str, options: KvOptions) -> Result<Self, KvError> {
+        let path = PathBuf::from(filename);
+        // A compaction that crashed before its rename leaves a segment nobody will use
+        let _ = fs::remove_file(compaction_path(&path));
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+        let mut store = KvStore {
+            path,
+            file: file.try_clone()?,
+            options,
+            index: HashMap::new(),
+            end: LOG_HEADER_LEN,
+            live_bytes: 0,
+            compactions: 0,
+            compaction_error: None,
+        };
+
+        let file_len = file.metadata()?.len();
+        if file_len == 0 {
+            file.write_all(&log_header())?;
+            file.sync_data()?;
+            return Ok(store);
+        }
+        let mut reader = LogReader::new(BufReader::new(&mut file), file_len)?;
+        while let Some((offset, record)) = reader.next_record()? {
+            let len = record.encoded_len();
+            match record {
+                Record::Put { key, .. } => store.set_pointer(key, LogPointer { offset, len }),
+                Record::Delete { key } => store.remove_pointer(&key),
+            }
+        }
+        store.end = reader.offset();
+        if store.end < file_len {
+            file.set_len(store.end)?;
+            file.sync_data()?;
+        }
+        Ok(store)
+    }
+
+    /// Number of live keys
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// The live keys, in no particular order
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(String::as_str)
+    }
+
+    /// Size of the log in bytes
+    pub fn log_len(&self) -> u64 {
+        self.end
+    }
+
+    /// Bytes of the log taken by overwritten values, deleted keys and tombstones
+    pub fn dead_bytes(&self) -> u64 {
+        self.end - LOG_HEADER_LEN - self.live_bytes
+    }
+
+    /// Fraction of the log's records that is dead, from 0.0 to 1.0
+    pub fn dead_ratio(&self) -> f64 {
+        match self.end - LOG_HEADER_LEN {
+            0 => 0.0,
+            records => self.dead_bytes() as f64 / records as f64,
+        }
+    }
+
+    /// Number of compactions since the store was opened
+    pub fn compactions(&self) -> u64 {
+        self.compactions
+    }
+
+    /// Takes the error of the last automatic compaction, if it failed. Writes do not report it,
+    /// since the write itself was done by the time compaction ran.
+    pub fn take_compaction_error(&mut self) -> Option<KvError> {
+        self.compaction_error.take()
+    }
+
+    /// Reads the current value of `key` from the log
+    pub fn get(&mut self, key: &str) -> Result<Option<i32>, KvError> {
+        let Some(pointer) = self.index.get(key) else {
+            return Ok(None);
+        };
+        // The value is the last 4 bytes of a put record
+        let mut value = [0u8; 4];
+        self.file.seek(SeekFrom::Start(pointer.offset + pointer.len - 4))?;
+        self.file.read_exact(&mut value)?;
+        Ok(Some(i32::from_le_bytes(value)))
+    }
+
+    /// Sets `key` to `value` by appending one record to the log. Keys longer than `MAX_KEY_LEN`
+    /// bytes are rejected.
+    pub fn put(&mut self, key: &str, value: i32) -> Result<(), KvError> {
+        check_key(key)?;
+        let pointer = self.append(&Record::Put { key: key.to_string(), value })?;
+        self.set_pointer(key.to_string(), pointer);
+        self.maybe_compact();
+        Ok(())
+    }
+
+    /// Removes `key` by appending a tombstone. Returns whether the key was there; deleting a
+    /// missing key writes nothing.
+    pub fn delete(&mut self, key: &str) -> Result<bool, KvError> {
+        if !self.index.contains_key(key) {
+            return Ok(false);
+        }
+        self.append(&Record::Delete { key: key.to_string() })?;
+        self.remove_pointer(key);
+        self.maybe_compact();
+        Ok(true)
+    }
+
+    /// Rewrites the log with only the live records and swaps it in atomically
+    pub fn compact(&mut self) -> Result<(), KvError> {
+        let new_path = compaction_path(&self.path);
+        let result = self.write_compacted(&new_path);
+        let (new_file, new_index) = match result {
+            Ok(compacted) => compacted,
+            Err(e) => {
+                let _ = fs::remove_file(&new_path);
+                return Err(e);
+            }
+        };
+        fs::rename(&new_path, &self.path)?;
+        // The old log is gone from the directory now, so switch over before anything else can fail
+        self.file = new_file;
+        self.end = LOG_HEADER_LEN + self.live_bytes;
+        self.index = new_index;
+        self.compactions += 1;
+        sync_parent_dir(&self.path)?;
+        Ok(())
+    }
+
+    /// Copies the live records, oldest first, into a new synced segment at `new_path`. Returns
+    /// the segment and the index into it.
+    fn write_compacted(&mut self, new_path: &Path) -> Result<(File, HashMap<String, LogPointer>), KvError> {
+        let new_file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(new_path)?;
+        let mut writer = BufWriter::new(&new_file);
+        writer.write_all(&log_header())?;
+        let mut new_index = HashMap::with_capacity(self.index.len());
+        let mut end = LOG_HEADER_LEN;
+
+        self.file.seek(SeekFrom::Start(0))?;
+        let mut reader = LogReader::new(BufReader::new(&mut self.file), self.end)?;
+        let mut bytes = Vec::new();
+        while let Some((offset, record)) = reader.next_record()? {
+            if self.index.get(record.key()).is_none_or(|pointer| pointer.offset != offset) {
+                continue;
+            }
+            bytes.clear();
+            record.encode(&mut bytes);
+            writer.write_all(&bytes)?;
+            let len = bytes.len() as u64;
+            let Record::Put { key, .. } = record else { unreachable!("the index only points to puts") };
+            new_index.insert(key, LogPointer { offset: end, len });
+            end += len;
+        }
+        writer.flush()?;
+        drop(writer);
+        new_file.sync_all()?;
+        Ok((new_file, new_index))
+    }
+
+    fn maybe_compact(&mut self) {
+        if self.end >= self.options.min_compaction_len && self.dead_ratio() > self.options.compaction_threshold {
+            if let Err(e) = self.compact() {
+                self.compaction_error = Some(e);
+            }
+        }
+    }
+
+    fn set_pointer(&mut self, key: String, pointer: LogPointer) {
+        self.live_bytes += pointer.len;
+        if let Some(old) = self.index.insert(key, pointer) {
+            self.live_bytes -= old.len;
+        }
+    }
+
+    fn remove_pointer(&mut self, key: &str) {
+        if let Some(old) = self.index.remove(key) {
+            self.live_bytes -= old.len;
+        }
+    }
+
+    fn append(&mut self, record: &Record) -> Result<LogPointer, KvError> {
+        let mut bytes = Vec::with_capacity(record.encoded_len() as usize);
+        record.encode(&mut bytes);
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(&bytes)?;
+        let pointer = LogPointer { offset: self.end, len: bytes.len() as u64 };
+        self.end += pointer.len;
+        Ok(pointer)
+    }
+}
+
+/// Where `compact` writes the new segment before renaming it over the log at `path`
+fn compaction_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!(".{}.compact", file_name))
+}
+
+/// Makes a rename durable by fsyncing the directory that holds `path`
+#[cfg(unix)]
+pub(crate) fn sync_parent_dir(path: &Path) -> Result<(), Error> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened (and fsynced) like files outside of unix
+#[cfg(not(unix))]
+pub(crate) fn sync_parent_dir(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
@@ -0,0 +1,191 @@
+use std::io::{ErrorKind, Read};
+
+use crate::error::KvError;
+
+// A log file is a 5-byte header followed by records, oldest first:
+//
+//   magic     4 bytes  "SRDH"
+//   version   1 byte   (1)
+//
+// Every record starts with a kind byte and the key, and a put also carries the value:
+//
+//   kind      u8       1 = put, 0 = delete (a "tombstone")
+//   key len   u32      little-endian, at most MAX_KEY_LEN
+//   key       key len bytes of UTF-8
+//   value     i32      little-endian, puts only
+//
+// Replaying the records in order gives the current map: the last record for a key wins, and a
+// tombstone means the key is gone. Records are only ever appended, so a crash can at worst leave
+// the last record incomplete; readers stop before it. No record is longer than MAX_RECORD_LEN,
+// so a key length above MAX_KEY_LEN is corruption rather than a torn write.
+
+/// Identifies a key-value log
+pub const MAGIC: [u8; 4] = *b"SRDH";
+
+/// The only log version this code knows how to read
+pub const VERSION: u8 = 1;
+
+/// Size of the log header in bytes; the first record starts at this offset
+pub const LOG_HEADER_LEN: u64 = 5;
+
+const KIND_DELETE: u8 = 0;
+const KIND_PUT: u8 = 1;
+
+/// Size of a record before its key
+const RECORD_PREFIX_LEN: u64 = 1 + 4;
+
+/// Longest key a log can hold, in bytes
+pub const MAX_KEY_LEN: usize = 1 << 16;
+
+/// Size of the largest possible record
+pub const MAX_RECORD_LEN: u64 = RECORD_PREFIX_LEN + MAX_KEY_LEN as u64 + 4;
+
+/// Fails for keys too long to be written to a log
+pub(crate) fn check_key(key: &str) -> Result<(), KvError> {
+    if key.len() > MAX_KEY_LEN {
+        return Err(KvError::KeyTooLong(key.len()));
+    }
+    Ok(())
+}
+
+/// One entry of the log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    Put { key: String, value: i32 },
+    Delete { key: String },
+}
+
+impl Record {
+    pub fn key(&self) -> &str {
+        match self {
+            Record::Put { key, .. } | Record::Delete { key } => key,
+        }
+    }
+
+    /// Number of bytes `encode` appends
+    pub fn encoded_len(&self) -> u64 {
+        let value_len = match self {
+            Record::Put { .. } => 4,
+            Record::Delete { .. } => 0,
+        };
+        RECORD_PREFIX_LEN + self.key().len() as u64 + value_len
+    }
+
+    /// Appends the record's bytes to `out`
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let kind = match self {
+            Record::Put { .. } => KIND_PUT,
+            Record::Delete { .. } => KIND_DELETE,
+        };
+        out.push(kind);
+        out.extend_from_slice(&(self.key().len() as u32).to_le_bytes());
+        out.extend_from_slice(self.key().as_bytes());
+        if let Record::Put { value, .. } = self {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    /// Parses a record that takes up all of `bytes`, as written by `encode`
+    pub fn decode(bytes: &[u8]) -> Result<Self, KvError> {
+        let corrupt = || KvError::Corrupt("record has the wrong length");
+        let prefix = bytes.get(..RECORD_PREFIX_LEN as usize).ok_or_else(corrupt)?;
+        let key_len = u32::from_le_bytes(prefix[1..5].try_into().unwrap()) as usize;
+        let value_len = match prefix[0] {
+            KIND_PUT => 4,
+            KIND_DELETE => 0,
+            _ => return Err(KvError::Corrupt("unknown record kind")),
+        };
+        let key_end = RECORD_PREFIX_LEN as usize + key_len;
+        if key_end.checked_add(value_len) != Some(bytes.len()) {
+            return Err(corrupt());
+        }
+        let key = String::from_utf8(bytes[RECORD_PREFIX_LEN as usize..key_end].to_vec())
+            .map_err(|_| KvError::Corrupt("key is not valid UTF-8"))?;
+        Ok(match prefix[0] {
+            KIND_PUT => Record::Put { key, value: i32::from_le_bytes(bytes[key_end..].try_into().unwrap()) },
+            _ => Record::Delete { key },
+        })
+    }
+}
+
+/// The header every log starts with
+pub fn log_header() -> [u8; LOG_HEADER_LEN as usize] {
+    let mut header = [0u8; LOG_HEADER_LEN as usize];
+    header[0..4].copy_from_slice(&MAGIC);
+    header[4] = VERSION;
+    header
+}
+
+/// Reads the records of a log one by one
+pub struct LogReader<R> {
+    reader: R,
+    offset: u64,
+    file_len: u64,
+}
+
+impl<R: Read> LogReader<R> {
+    /// Checks the header of a log of `file_len` bytes at the start of `reader`
+    pub fn new(mut reader: R, file_len: u64) -> Result<Self, KvError> {
+        let mut header = [0u8; LOG_HEADER_LEN as usize];
+        if file_len < LOG_HEADER_LEN {
+            return Err(KvError::BadMagic);
+        }
+        reader.read_exact(&mut header)?;
+        if header[0..4] != MAGIC {
+            return Err(KvError::BadMagic);
+        }
+        if header[4] != VERSION {
+            return Err(KvError::UnsupportedVersion(header[4]));
+        }
+        Ok(LogReader { reader, offset: LOG_HEADER_LEN, file_len })
+    }
+
+    /// Offset just past the last complete record read so far. Once `next_record` returns
+    /// `None`, anything from here on is an incomplete record.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Reads the next record and the offset it starts at, or `None` at the end of the log,
+    /// including when the last record was cut short. A record that cannot be a torn last one is
+    /// an error.
+    pub fn next_record(&mut self) -> Result<Option<(u64, Record)>, KvError> {
+        let remaining = self.file_len - self.offset;
+        if remaining < RECORD_PREFIX_LEN {
+            return Ok(None);
+        }
+        let mut prefix = [0u8; RECORD_PREFIX_LEN as usize];
+        self.reader.read_exact(&mut prefix)?;
+        let kind = prefix[0];
+        let key_len = u32::from_le_bytes(prefix[1..5].try_into().unwrap()) as u64;
+        let value_len = match kind {
+            KIND_PUT => 4,
+            KIND_DELETE => 0,
+            _ => return Err(KvError::Corrupt("unknown record kind")),
+        };
+        // A torn record is at most MAX_RECORD_LEN bytes from the end; a longer one is damage
+        // somewhere in the log, and treating it as torn would cut off every record after it
+        if key_len > MAX_KEY_LEN as u64 {
+            return Err(KvError::Corrupt("key longer than the maximum"));
+        }
+        let record_len = RECORD_PREFIX_LEN + key_len + value_len;
+        if record_len > remaining {
+            return Ok(None);
+        }
+
+        let mut rest = vec![0u8; (key_len + value_len) as usize];
+        match self.reader.read_exact(&mut rest) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            other => other?,
+        }
+        let value = rest.split_off(key_len as usize);
+        let key = String::from_utf8(rest).map_err(|_| KvError::Corrupt("key is not valid UTF-8"))?;
+        let record = match kind {
+            KIND_PUT => Record::Put { key, value: i32::from_le_bytes(value.try_into().unwrap()) },
+            _ => Record::Delete { key },
+        };
+        let offset = self.offset;
+        self.offset += record_len;
+        Ok(Some((offset, record)))
+    }
+}
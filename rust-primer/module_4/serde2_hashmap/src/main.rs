@@ -13,14 +13,14 @@ fn main() {
         ("Mars".to_string(), 5),
     ]);
     if serialize {
-        serialize_data_to_disk(data, &filename).unwrap();
+        serialize_data_to_disk(data, filename).unwrap();
     } else {
-        let deserialized_data = deserialize_data_from_disk(&filename);
+        let deserialized_data = deserialize_data_from_disk(filename);
         println!("The size of the hashmap is: {}", deserialized_data.len());
         println!("This is the data:");
         for (key, value) in &deserialized_data {
             println!("{}: {}", key, value);
         }
-        assert_eq!(data == deserialized_data, true);
+        assert_eq!(data, deserialized_data);
     }
 }
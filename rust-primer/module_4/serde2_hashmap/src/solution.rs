@@ -1,12 +1,57 @@
+pub mod bloom;
+pub mod crc32;
+pub mod error;
+pub mod kv_store;
+pub mod log;
+pub mod sstable;
+pub mod wal;
+
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, Read, Write};
-use std::io::Error;
+use std::io::{BufReader, BufWriter, Write};
+use std::io::{Error, ErrorKind};
+
+pub use bloom::BloomFilter;
+pub use crc32::crc32;
+pub use error::KvError;
+pub use kv_store::{KvOptions, KvStore};
+pub use sstable::{write_sstable, write_sstable_with_block_size, write_sstable_with_options, SSTable, SSTableOptions,
+                  DEFAULT_BLOCK_SIZE, DEFAULT_FALSE_POSITIVE_RATE, SSTABLE_MAGIC, SSTABLE_VERSION};
+pub use wal::{replay_wal, wal_path_for, DurableMap, SyncPolicy, WAL_HEADER_LEN, WAL_MAGIC, WAL_VERSION};
+pub use log::{log_header, LogReader, Record, LOG_HEADER_LEN, MAGIC, MAX_KEY_LEN, MAX_RECORD_LEN, VERSION};
 
+/// Writes `data` to `filename` as a key-value log with one put per entry, so the file can also be
+/// opened as a `KvStore`. Fails with `InvalidInput` if a key is longer than `MAX_KEY_LEN` bytes.
 pub fn serialize_data_to_disk(data: HashMap<String, i32>, filename: &str) -> Result<(), Error> {
-    panic!("TODO milestone primer-mod5");
+    let mut bytes = log_header().to_vec();
+    for (key, value) in data {
+        if key.len() > MAX_KEY_LEN {
+            return Err(Error::new(ErrorKind::InvalidInput, KvError::KeyTooLong(key.len()).to_string()));
+        }
+        Record::Put { key, value }.encode(&mut bytes);
+    }
+    let mut writer = BufWriter::new(File::create(filename)?);
+    writer.write_all(&bytes)?;
+    writer.flush()
 }
 
+/// Reads the map stored in `filename` by replaying its log. Panics if the file cannot be read;
+/// use `try_deserialize_data_from_disk` to handle that instead.
 pub fn deserialize_data_from_disk(filename: &str) -> HashMap<String, i32> {
-    panic!("TODO milestone primer-mod5");
+    try_deserialize_data_from_disk(filename).unwrap_or_else(|e| panic!("cannot read {}: {}", filename, e))
+}
+
+/// Like `deserialize_data_from_disk`, returning an error instead of panicking
+pub fn try_deserialize_data_from_disk(filename: &str) -> Result<HashMap<String, i32>, KvError> {
+    let file = File::open(filename)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = LogReader::new(BufReader::new(file), file_len)?;
+    let mut map = HashMap::new();
+    while let Some((_, record)) = reader.next_record()? {
+        match record {
+            Record::Put { key, value } => map.insert(key, value),
+            Record::Delete { key } => map.remove(&key),
+        };
+    }
+    Ok(map)
 }
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Error, Read, Seek, SeekFrom, Write};
+use std::ops::{Bound, RangeBounds};
+
+use crate::bloom::BloomFilter;
+use crate::error::KvError;
+
+// An SSTable ("sorted string table") holds a map with its entries sorted by key, so a reader can
+// find one key without loading everything:
+//
+//   header    5 bytes   magic "SRDT", version (2)
+//   blocks    entries sorted by key, cut into blocks of about `block size` bytes:
+//               key len   u32
+//               key       key len bytes of UTF-8
+//               value     i32
+//   index     one entry per block, in order:
+//               key len   u32
+//               key       first key of the block
+//               offset    u64   where the block starts in the file
+//               len       u32   block length in bytes
+//   filter    a Bloom filter over all the keys (see `bloom`)
+//   footer    40 bytes, always the last bytes of the file:
+//               index offset   u64
+//               index len      u64
+//               filter offset  u64
+//               filter len     u64
+//               entry count    u64
+//
+// All integers are little-endian. Opening a table reads the footer, the (small) index and the
+// filter. A lookup first asks the filter, which rules out most absent keys without any further
+// I/O; otherwise it binary-searches the index for the one block that could hold the key and reads
+// only that block.
+
+/// Identifies an SSTable file
+pub const SSTABLE_MAGIC: [u8; 4] = *b"SRDT";
+
+/// The only SSTable version this code knows how to read. Version 1 had no Bloom filter.
+pub const SSTABLE_VERSION: u8 = 2;
+
+/// Target size of a block in bytes when none is given
+pub const DEFAULT_BLOCK_SIZE: usize = 4096;
+
+/// Bloom filter false-positive rate when none is given
+pub const DEFAULT_FALSE_POSITIVE_RATE: f64 = 0.01;
+
+const SSTABLE_HEADER_LEN: u64 = 5;
+const FOOTER_LEN: u64 = 40;
+
+/// How `write_sstable_with_options` lays out a table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SSTableOptions {
+    /// A block is closed once it holds at least this many bytes
+    pub block_size: usize,
+    /// Fraction of absent keys the Bloom filter lets through to a block read
+    pub false_positive_rate: f64,
+}
+
+impl Default for SSTableOptions {
+    fn default() -> Self {
+        SSTableOptions { block_size: DEFAULT_BLOCK_SIZE, false_positive_rate: DEFAULT_FALSE_POSITIVE_RATE }
+    }
+}
+
+/// Writes `data` to `filename` as an SSTable with the default options
+pub fn write_sstable(data: &HashMap<String, i32>, filename: &str) -> Result<(), Error> {
+    write_sstable_with_options(data, filename, &SSTableOptions::default())
+}
+
+/// Like `write_sstable`; a block is closed once it holds at least `block_size` bytes
+pub fn write_sstable_with_block_size(data: &HashMap<String, i32>, filename: &str, block_size: usize) -> Result<(), Error> {
+    write_sstable_with_options(data, filename, &SSTableOptions { block_size, ..SSTableOptions::default() })
+}
+
+/// Writes `data` to `filename` as an SSTable laid out according to `options`
+pub fn write_sstable_with_options(data: &HashMap<String, i32>, filename: &str, options: &SSTableOptions) -> Result<(), Error> {
+    let block_size = options.block_size;
+    let mut entries: Vec<(&String, &i32)> = data.iter().collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+
+    let mut writer = BufWriter::new(File::create(filename)?);
+    writer.write_all(&SSTABLE_MAGIC)?;
+    writer.write_all(&[SSTABLE_VERSION])?;
+    let mut offset = SSTABLE_HEADER_LEN;
+    let mut index = Vec::new();
+    let mut block = Vec::with_capacity(block_size);
+    let mut rest = &entries[..];
+    while let Some(&(first_key, _)) = rest.first() {
+        let mut taken = 0;
+        for (key, value) in rest {
+            block.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            block.extend_from_slice(key.as_bytes());
+            block.extend_from_slice(&value.to_le_bytes());
+            taken += 1;
+            if block.len() >= block_size {
+                break;
+            }
+        }
+        index.extend_from_slice(&(first_key.len() as u32).to_le_bytes());
+        index.extend_from_slice(first_key.as_bytes());
+        index.extend_from_slice(&offset.to_le_bytes());
+        index.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        writer.write_all(&block)?;
+        offset += block.len() as u64;
+        block.clear();
+        rest = &rest[taken..];
+    }
+
+    let mut filter = BloomFilter::with_rate(data.len(), options.false_positive_rate);
+    for key in data.keys() {
+        filter.insert(key);
+    }
+    let mut filter_bytes = Vec::new();
+    filter.encode(&mut filter_bytes);
+
+    writer.write_all(&index)?;
+    writer.write_all(&filter_bytes)?;
+    let filter_offset = offset + index.len() as u64;
+    for field in [offset, index.len() as u64, filter_offset, filter_bytes.len() as u64, data.len() as u64] {
+        writer.write_all(&field.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Where one block lives and the first key in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BlockHandle {
+    first_key: String,
+    offset: u64,
+    len: u32,
+}
+
+/// An open SSTable that keeps only its index in memory
+pub struct SSTable {
+    file: File,
+    index: Vec<BlockHandle>,
+    filter: BloomFilter,
+    len: u64,
+    blocks_read: u64,
+}
+
+impl SSTable {
+    /// Opens `filename`, reading its footer, index and filter but no blocks
+    pub fn open(filename: &str) -> Result<Self, KvError> {
+        let mut file = File::open(filename)?;
+        let file_len = file.metadata()?.len();
+        if file_len < SSTABLE_HEADER_LEN + FOOTER_LEN {
+            return Err(KvError::BadMagic);
+        }
+        let mut header = [0u8; SSTABLE_HEADER_LEN as usize];
+        file.read_exact(&mut header)?;
+        if header[0..4] != SSTABLE_MAGIC {
+            return Err(KvError::BadMagic);
+        }
+        if header[4] != SSTABLE_VERSION {
+            return Err(KvError::UnsupportedVersion(header[4]));
+        }
+
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        file.seek(SeekFrom::Start(file_len - FOOTER_LEN))?;
+        file.read_exact(&mut footer)?;
+        let index_offset = u64::from_le_bytes(footer[0..8].try_into().unwrap());
+        let index_len = u64::from_le_bytes(footer[8..16].try_into().unwrap());
+        let filter_offset = u64::from_le_bytes(footer[16..24].try_into().unwrap());
+        let filter_len = u64::from_le_bytes(footer[24..32].try_into().unwrap());
+        let len = u64::from_le_bytes(footer[32..40].try_into().unwrap());
+        // The index and the filter sit back to back right before the footer
+        if index_offset < SSTABLE_HEADER_LEN
+            || index_offset.checked_add(index_len) != Some(filter_offset)
+            || filter_offset.checked_add(filter_len) != Some(file_len - FOOTER_LEN)
+        {
+            return Err(KvError::Corrupt("footer does not point to the index and filter"));
+        }
+
+        let mut bytes = vec![0u8; (index_len + filter_len) as usize];
+        file.seek(SeekFrom::Start(index_offset))?;
+        file.read_exact(&mut bytes)?;
+        let index = parse_index(&bytes[..index_len as usize], index_offset)?;
+        let filter = BloomFilter::decode(&bytes[index_len as usize..])?;
+        Ok(SSTable { file, index, filter, len, blocks_read: 0 })
+    }
+
+    /// Number of entries in the table
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of blocks in the table
+    pub fn block_count(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Number of blocks read from disk since the table was opened
+    pub fn blocks_read(&self) -> u64 {
+        self.blocks_read
+    }
+
+    /// The filter over the table's keys
+    pub fn bloom_filter(&self) -> &BloomFilter {
+        &self.filter
+    }
+
+    /// `false` means the table definitely does not hold `key`; answered from memory
+    pub fn may_contain(&self, key: &str) -> bool {
+        self.filter.may_contain(key)
+    }
+
+    /// Looks up `key`, reading at most one block, and none if the filter rules the key out
+    pub fn get(&mut self, key: &str) -> Result<Option<i32>, KvError> {
+        if !self.filter.may_contain(key) {
+            return Ok(None);
+        }
+        let Some(block) = self.block_for(key) else {
+            return Ok(None);
+        };
+        let entries = self.read_block(block)?;
+        Ok(entries.binary_search_by(|(k, _)| k.as_str().cmp(key)).ok().map(|i| entries[i].1))
+    }
+
+    /// Returns every entry whose key lies in `range`, in key order
+    pub fn range<'a>(&mut self, range: impl RangeBounds<&'a str>) -> Result<Vec<(String, i32)>, KvError> {
+        let first_block = match range.start_bound() {
+            Bound::Included(start) | Bound::Excluded(start) => self.block_for(start).unwrap_or(0),
+            Bound::Unbounded => 0,
+        };
+        let past_end = |key: &str| match range.end_bound() {
+            Bound::Included(end) => key > *end,
+            Bound::Excluded(end) => key >= *end,
+            Bound::Unbounded => false,
+        };
+        let mut found = Vec::new();
+        for block in first_block..self.index.len() {
+            if past_end(&self.index[block].first_key) {
+                break;
+            }
+            for (key, value) in self.read_block(block)? {
+                if past_end(&key) {
+                    return Ok(found);
+                }
+                if range.contains(&key.as_str()) {
+                    found.push((key, value));
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Returns every entry whose key starts with `prefix`, in key order
+    pub fn scan_prefix(&mut self, prefix: &str) -> Result<Vec<(String, i32)>, KvError> {
+        let mut found = Vec::new();
+        let first_block = self.block_for(prefix).unwrap_or(0);
+        for block in first_block..self.index.len() {
+            // Keys with the prefix sort right after the prefix itself and before anything else
+            if self.index[block].first_key.as_str() > prefix && !self.index[block].first_key.starts_with(prefix) {
+                break;
+            }
+            for (key, value) in self.read_block(block)? {
+                if key.starts_with(prefix) {
+                    found.push((key, value));
+                } else if key.as_str() > prefix {
+                    return Ok(found);
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// The block that would hold `key`: the last one whose first key is not after it
+    fn block_for(&self, key: &str) -> Option<usize> {
+        self.index.partition_point(|block| block.first_key.as_str() <= key).checked_sub(1)
+    }
+
+    fn read_block(&mut self, block: usize) -> Result<Vec<(String, i32)>, KvError> {
+        let handle = &self.index[block];
+        let mut bytes = vec![0u8; handle.len as usize];
+        self.file.seek(SeekFrom::Start(handle.offset))?;
+        self.file.read_exact(&mut bytes)?;
+        self.blocks_read += 1;
+        parse_block(&bytes)
+    }
+}
+
+fn parse_index(bytes: &[u8], index_offset: u64) -> Result<Vec<BlockHandle>, KvError> {
+    let corrupt = || KvError::Corrupt("index is cut short");
+    let mut index: Vec<BlockHandle> = Vec::new();
+    let mut position = 0;
+    let mut expected_offset = SSTABLE_HEADER_LEN;
+    while position < bytes.len() {
+        let key_len = u32::from_le_bytes(bytes.get(position..position + 4).ok_or_else(corrupt)?.try_into().unwrap()) as usize;
+        let key_end = position + 4 + key_len;
+        let key = bytes.get(position + 4..key_end).ok_or_else(corrupt)?;
+        let handle = bytes.get(key_end..key_end + 12).ok_or_else(corrupt)?;
+        let first_key = String::from_utf8(key.to_vec()).map_err(|_| KvError::Corrupt("key is not valid UTF-8"))?;
+        let offset = u64::from_le_bytes(handle[0..8].try_into().unwrap());
+        let len = u32::from_le_bytes(handle[8..12].try_into().unwrap());
+        // Blocks are back to back between the header and the index
+        if offset != expected_offset {
+            return Err(KvError::Corrupt("blocks are not back to back"));
+        }
+        if index.last().is_some_and(|last| last.first_key >= first_key) {
+            return Err(KvError::Corrupt("index keys are not sorted"));
+        }
+        expected_offset += len as u64;
+        index.push(BlockHandle { first_key, offset, len });
+        position = key_end + 12;
+    }
+    if expected_offset != index_offset {
+        return Err(KvError::Corrupt("blocks do not end where the index starts"));
+    }
+    Ok(index)
+}
+
+fn parse_block(bytes: &[u8]) -> Result<Vec<(String, i32)>, KvError> {
+    let corrupt = || KvError::Corrupt("block is cut short");
+    let mut entries = Vec::new();
+    let mut position = 0;
+    while position < bytes.len() {
+        let key_len = u32::from_le_bytes(bytes.get(position..position + 4).ok_or_else(corrupt)?.try_into().unwrap()) as usize;
+        let key_end = position + 4 + key_len;
+        let key = bytes.get(position + 4..key_end).ok_or_else(corrupt)?;
+        let value = bytes.get(key_end..key_end + 4).ok_or_else(corrupt)?;
+        let key = String::from_utf8(key.to_vec()).map_err(|_| KvError::Corrupt("key is not valid UTF-8"))?;
+        entries.push((key, i32::from_le_bytes(value.try_into().unwrap())));
+        position = key_end + 4;
+    }
+    Ok(entries)
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use crate::crc32::crc32;
+use crate::error::KvError;
+use crate::kv_store::sync_parent_dir;
+use crate::log::{check_key, log_header, Record};
+use crate::try_deserialize_data_from_disk;
+
+// A `DurableMap` keeps the whole map in memory and makes every change durable in two places:
+//
+//   snapshot   the map as of the last checkpoint, in the key-value log format (see `log`)
+//   WAL        every put and delete since then, in order, in "<snapshot>.wal"
+//
+// A change is appended to the write-ahead log (and fsynced, depending on the `SyncPolicy`) before
+// it is applied in memory and acknowledged. Recovery loads the snapshot and replays the log over
+// it. A checkpoint writes a new snapshot atomically (temp file, fsync, rename) and only then
+// empties the log; replaying a log whose changes are already in the snapshot is harmless, since
+// applying the same changes in the same order again gives the same map.
+//
+// The log starts with a 5-byte header, magic "SRDW" and version (1), followed by frames:
+//
+//   crc       u32   CRC-32 of the length and the record
+//   len       u32   record length in bytes
+//   record    len bytes, a put or delete encoded as in `log`
+//
+// A crash in the middle of an append leaves a final frame that is cut short or fails its
+// checksum. Replay stops right before it and drops it, so recovery always yields the state after
+// some prefix of the writes, including every write that was fsynced. A write that fails, in the
+// append or in the fsync, is cut back off the log before the error is returned, so recovery never
+// sees a write the caller was told failed; if even that fails, the map refuses further writes
+// until a checkpoint replaces the log.
+
+/// Identifies a write-ahead log
+pub const WAL_MAGIC: [u8; 4] = *b"SRDW";
+
+/// The only write-ahead log version this code knows how to read
+pub const WAL_VERSION: u8 = 1;
+
+/// Size of the write-ahead log header in bytes
+pub const WAL_HEADER_LEN: u64 = 5;
+
+/// Size of a frame before its record
+const FRAME_PREFIX_LEN: usize = 4 + 4;
+
+/// When a `DurableMap` fsyncs its write-ahead log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// After every write, before acknowledging it; nothing acknowledged is ever lost
+    Always,
+    /// After every n writes; a power failure can lose up to n - 1 acknowledged writes
+    EveryN(u32),
+    /// Only on `sync` and `checkpoint`; the OS decides when the rest reaches the disk
+    Never,
+}
+
+/// The write-ahead log that goes with the snapshot at `filename`
+pub fn wal_path_for(filename: &str) -> PathBuf {
+    PathBuf::from(format!("{}.wal", filename))
+}
+
+/// Splits a write-ahead log into its records. Returns them along with the length of the log up
+/// to the end of the last intact frame; anything after that is a torn write.
+pub fn replay_wal(bytes: &[u8]) -> Result<(Vec<Record>, usize), KvError> {
+    if bytes.len() < WAL_HEADER_LEN as usize || bytes[0..4] != WAL_MAGIC {
+        return Err(KvError::BadMagic);
+    }
+    if bytes[4] != WAL_VERSION {
+        return Err(KvError::UnsupportedVersion(bytes[4]));
+    }
+    let mut records = Vec::new();
+    let mut offset = WAL_HEADER_LEN as usize;
+    while let Some(prefix) = bytes.get(offset..offset + FRAME_PREFIX_LEN) {
+        let stored = u32::from_le_bytes(prefix[0..4].try_into().unwrap());
+        let len = u32::from_le_bytes(prefix[4..8].try_into().unwrap()) as usize;
+        let end = offset + FRAME_PREFIX_LEN + len;
+        let Some(checked) = bytes.get(offset + 4..end) else { break };
+        if crc32(checked) != stored {
+            break;
+        }
+        // The checksum matched, so a bad record was written that way, not torn
+        records.push(Record::decode(&checked[4..])?);
+        offset = end;
+    }
+    Ok((records, offset))
+}
+
+/// A map whose changes are made durable through a write-ahead log
+pub struct DurableMap {
+    snapshot_path: PathBuf,
+    wal: File,
+    /// Length of the write-ahead log up to the last complete frame
+    wal_len: u64,
+    policy: SyncPolicy,
+    map: HashMap<String, i32>,
+    unsynced: u32,
+    syncs: u64,
+    /// Set when a failed write could not be cut off the log
+    poisoned: bool,
+    sync_hook: Option<Box<dyn FnMut() -> Result<(), Error>>>,
+}
+
+impl DurableMap {
+    /// Recovers the map from the snapshot at `filename` and its write-ahead log, creating either
+    /// if it does not exist yet. A torn final frame is cut off the log.
+    pub fn open(filename: &str, policy: SyncPolicy) -> Result<Self, KvError> {
+        let mut map = match try_deserialize_data_from_disk(filename) {
+            Ok(map) => map,
+            Err(KvError::Io(e)) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        let wal_path = wal_path_for(filename);
+        let mut wal = OpenOptions::new().read(true).append(true).create(true).open(&wal_path)?;
+        let bytes = fs::read(&wal_path)?;
+        // A crash while creating the log can leave part of the header, but no writes yet
+        let wal_len = if bytes.len() < WAL_HEADER_LEN as usize && wal_header().starts_with(&bytes) {
+            wal.set_len(0)?;
+            wal.write_all(&wal_header())?;
+            wal.sync_data()?;
+            WAL_HEADER_LEN
+        } else {
+            let (records, valid_len) = replay_wal(&bytes)?;
+            for record in records {
+                apply(&mut map, record);
+            }
+            if valid_len < bytes.len() {
+                wal.set_len(valid_len as u64)?;
+                wal.sync_data()?;
+            }
+            valid_len as u64
+        };
+        Ok(DurableMap {
+            snapshot_path: PathBuf::from(filename),
+            wal,
+            wal_len,
+            policy,
+            map,
+            unsynced: 0,
+            syncs: 0,
+            poisoned: false,
+            sync_hook: None,
+        })
+    }
+
+    /// Calls `hook` before every fsync of the log. If the hook returns an error the fsync counts
+    /// as failed, which lets tests simulate a disk that stops accepting writes.
+    pub fn with_sync_hook<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut() -> Result<(), Error> + 'static,
+    {
+        self.sync_hook = Some(Box::new(hook));
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<i32> {
+        self.map.get(key).copied()
+    }
+
+    /// Number of keys in the map
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The current contents of the map
+    pub fn as_map(&self) -> &HashMap<String, i32> {
+        &self.map
+    }
+
+    /// Number of times the write-ahead log has been fsynced for writes since the map was opened
+    pub fn syncs(&self) -> u64 {
+        self.syncs
+    }
+
+    /// Sets `key` to `value`. Once this returns, the write is in the log (and on disk, as far as
+    /// the sync policy promises).
+    pub fn put(&mut self, key: &str, value: i32) -> Result<(), KvError> {
+        check_key(key)?;
+        self.log(Record::Put { key: key.to_string(), value })
+    }
+
+    /// Removes `key`, returning whether it was there. Deleting a missing key logs nothing.
+    pub fn delete(&mut self, key: &str) -> Result<bool, KvError> {
+        if !self.map.contains_key(key) {
+            return Ok(false);
+        }
+        self.log(Record::Delete { key: key.to_string() })?;
+        Ok(true)
+    }
+
+    /// Fsyncs every write so far, whatever the sync policy
+    pub fn sync(&mut self) -> Result<(), KvError> {
+        if let Some(hook) = &mut self.sync_hook {
+            hook()?;
+        }
+        self.wal.sync_data()?;
+        self.unsynced = 0;
+        self.syncs += 1;
+        Ok(())
+    }
+
+    /// Writes the whole map as the new snapshot and empties the write-ahead log. This also lifts
+    /// the ban on writes after a failed write could not be undone.
+    pub fn checkpoint(&mut self) -> Result<(), KvError> {
+        let mut bytes = log_header().to_vec();
+        for (key, &value) in &self.map {
+            Record::Put { key: key.clone(), value }.encode(&mut bytes);
+        }
+        let tmp_path = checkpoint_path(&self.snapshot_path);
+        let result = (|| {
+            let mut tmp = File::create(&tmp_path)?;
+            tmp.write_all(&bytes)?;
+            tmp.sync_all()?;
+            fs::rename(&tmp_path, &self.snapshot_path)
+        })();
+        if let Err(e) = result {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e.into());
+        }
+        sync_parent_dir(&self.snapshot_path)?;
+
+        // The snapshot now holds every logged change, so the log can start over
+        self.wal.set_len(WAL_HEADER_LEN)?;
+        self.wal.sync_data()?;
+        self.wal_len = WAL_HEADER_LEN;
+        self.unsynced = 0;
+        self.poisoned = false;
+        Ok(())
+    }
+
+    fn log(&mut self, record: Record) -> Result<(), KvError> {
+        let mut frame = vec![0u8; 4];
+        let mut payload = Vec::with_capacity(record.encoded_len() as usize);
+        record.encode(&mut payload);
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&payload);
+        let crc = crc32(&frame[4..]);
+        frame[0..4].copy_from_slice(&crc.to_le_bytes());
+
+        if self.poisoned {
+            return Err(KvError::Poisoned);
+        }
+        if let Err(e) = self.wal.write_all(&frame) {
+            // Drop whatever part of the frame made it, so later frames do not land after garbage
+            self.roll_back();
+            return Err(e.into());
+        }
+        self.unsynced += 1;
+        let due = match self.policy {
+            SyncPolicy::Always => true,
+            SyncPolicy::EveryN(n) => self.unsynced >= n,
+            SyncPolicy::Never => false,
+        };
+        if due {
+            if let Err(e) = self.sync() {
+                // The caller is told this write failed, so recovery must not replay it
+                self.unsynced -= 1;
+                self.roll_back();
+                return Err(e);
+            }
+        }
+        self.wal_len += frame.len() as u64;
+        apply(&mut self.map, record);
+        Ok(())
+    }
+
+    /// Cuts the log back to the end of the last acknowledged write, or poisons the map if that
+    /// fails too
+    fn roll_back(&mut self) {
+        if self.wal.set_len(self.wal_len).is_err() {
+            self.poisoned = true;
+        }
+    }
+}
+
+fn wal_header() -> [u8; WAL_HEADER_LEN as usize] {
+    let mut header = [0u8; WAL_HEADER_LEN as usize];
+    header[0..4].copy_from_slice(&WAL_MAGIC);
+    header[4] = WAL_VERSION;
+    header
+}
+
+fn apply(map: &mut HashMap<String, i32>, record: Record) {
+    match record {
+        Record::Put { key, value } => map.insert(key, value),
+        Record::Delete { key } => map.remove(&key),
+    };
+}
+
+/// Where `checkpoint` writes the new snapshot before renaming it over the old one
+fn checkpoint_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!(".{}.checkpoint", file_name))
+}
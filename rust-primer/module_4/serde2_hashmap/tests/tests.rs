@@ -5,7 +5,7 @@ use rand::{distributions::Alphanumeric, Rng};
 #[test]
 fn test_serialize_deserialize_data_to_disk() {
     let n: i32 = generate_rand_num(1000, 2000);
-    let filename = "data_test.bin";
+    let filename = &temp_path("round_trip");
     let mut test_map: HashMap<String, i32> = HashMap::new();
 
     for _i in 0..n {
@@ -14,10 +14,11 @@ fn test_serialize_deserialize_data_to_disk() {
         test_map.insert(key, value);
     }
 
-    serialize_data_to_disk(test_map.clone(), &filename).unwrap();
-    let return_map = deserialize_data_from_disk(&filename);
+    serialize_data_to_disk(test_map.clone(), filename).unwrap();
+    let return_map = deserialize_data_from_disk(filename);
 
-    assert_eq!(return_map == test_map, true);
+    assert_eq!(return_map, test_map);
+    std::fs::remove_file(filename).unwrap();
 }
 
 fn generate_rand_string() -> String {
@@ -36,3 +37,753 @@ fn generate_rand_num(min: i32, max: i32) -> i32 {
     let num: i32 = rng.gen_range(min..max);
     num
 }
+
+/// A path in the system temp directory that is unique to this test process
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("serde2_hashmap_{}_{}", name, std::process::id()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+mod kv_store {
+    use super::{generate_rand_num, generate_rand_string, temp_path};
+    use solution::{KvError, KvStore, Record, LOG_HEADER_LEN, MAX_KEY_LEN, serialize_data_to_disk, deserialize_data_from_disk};
+    use std::collections::HashMap;
+    use std::fs;
+
+    #[test]
+    fn test_put_get_delete() {
+        let path = temp_path("kv_basic");
+        let _ = fs::remove_file(&path);
+        let mut store = KvStore::open(&path).unwrap();
+        assert!(store.is_empty());
+        assert_eq!(store.get("Mars").unwrap(), None);
+
+        store.put("Mars", 5).unwrap();
+        store.put("Venus", 7).unwrap();
+        store.put("Mars", 6).unwrap();
+        assert_eq!(store.get("Mars").unwrap(), Some(6));
+        assert_eq!(store.get("Venus").unwrap(), Some(7));
+        assert_eq!(store.len(), 2);
+
+        assert!(store.delete("Venus").unwrap());
+        assert!(!store.delete("Venus").unwrap());
+        assert_eq!(store.get("Venus").unwrap(), None);
+        store.put("", -1).unwrap();
+        assert_eq!(store.get("").unwrap(), Some(-1));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_index_is_rebuilt_on_open() {
+        let path = temp_path("kv_reopen");
+        let _ = fs::remove_file(&path);
+        let mut expected = HashMap::new();
+        {
+            let mut store = KvStore::open(&path).unwrap();
+            for i in 0..1000 {
+                let key = format!("key{}", i % 300);
+                if i % 7 == 0 {
+                    store.delete(&key).unwrap();
+                    expected.remove(&key);
+                } else {
+                    store.put(&key, i).unwrap();
+                    expected.insert(key, i);
+                }
+            }
+        }
+        let mut store = KvStore::open(&path).unwrap();
+        assert_eq!(store.len(), expected.len());
+        for i in 0..300 {
+            let key = format!("key{}", i);
+            assert_eq!(store.get(&key).unwrap(), expected.get(&key).copied());
+        }
+        // The log replays into the same map the old API reads
+        assert_eq!(deserialize_data_from_disk(&path), expected);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_update_is_one_append() {
+        let path = temp_path("kv_append");
+        let mut map = HashMap::new();
+        for i in 0..100000 {
+            map.insert(format!("key{:06}", i), i);
+        }
+        serialize_data_to_disk(map, &path).unwrap();
+        let mut store = KvStore::open(&path).unwrap();
+        assert_eq!(store.len(), 100000);
+
+        let before = fs::metadata(&path).unwrap().len();
+        store.put("key012345", -5).unwrap();
+        let record = Record::Put { key: "key012345".to_string(), value: -5 };
+        assert_eq!(fs::metadata(&path).unwrap().len(), before + record.encoded_len());
+        assert_eq!(store.get("key012345").unwrap(), Some(-5));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_torn_last_record_is_dropped() {
+        let path = temp_path("kv_torn");
+        let _ = fs::remove_file(&path);
+        {
+            let mut store = KvStore::open(&path).unwrap();
+            store.put("kept", 1).unwrap();
+            store.put("torn", 2).unwrap();
+        }
+        let full = fs::read(&path).unwrap();
+        let kept_end = full.len() - Record::Put { key: "torn".to_string(), value: 2 }.encoded_len() as usize;
+        for cut in kept_end..full.len() {
+            fs::write(&path, &full[..cut]).unwrap();
+            let mut store = KvStore::open(&path).unwrap();
+            assert_eq!(store.get("kept").unwrap(), Some(1));
+            assert_eq!(store.get("torn").unwrap(), None);
+            // The incomplete record is cut off, so new writes land after "kept"
+            assert_eq!(fs::metadata(&path).unwrap().len(), kept_end as u64);
+            store.put("new", 3).unwrap();
+            drop(store);
+            assert_eq!(KvStore::open(&path).unwrap().get("new").unwrap(), Some(3));
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_middle_record_is_an_error() {
+        let path = temp_path("kv_corrupt_middle");
+        let _ = fs::remove_file(&path);
+        {
+            let mut store = KvStore::open(&path).unwrap();
+            for i in 0..20 {
+                store.put(&format!("key{:02}", i), i).unwrap();
+            }
+        }
+        let full = fs::read(&path).unwrap();
+        // The key length of the 10th record, so it seems to run past the end of the file
+        let record_len = Record::Put { key: "key00".to_string(), value: 0 }.encoded_len() as usize;
+        let key_len_at = LOG_HEADER_LEN as usize + 9 * record_len + 1;
+        for key_len in [MAX_KEY_LEN as u32 + 1, u32::MAX] {
+            let mut corrupt = full.clone();
+            corrupt[key_len_at..key_len_at + 4].copy_from_slice(&key_len.to_le_bytes());
+            fs::write(&path, &corrupt).unwrap();
+            assert!(matches!(KvStore::open(&path), Err(KvError::Corrupt(_))));
+            assert_eq!(fs::read(&path).unwrap(), corrupt);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_key_too_long() {
+        let path = temp_path("kv_long_key");
+        let _ = fs::remove_file(&path);
+        let mut store = KvStore::open(&path).unwrap();
+        let longest = "k".repeat(MAX_KEY_LEN);
+        store.put(&longest, 1).unwrap();
+        assert!(matches!(store.put(&format!("{}k", longest), 2), Err(KvError::KeyTooLong(len)) if len == MAX_KEY_LEN + 1));
+        drop(store);
+        assert_eq!(KvStore::open(&path).unwrap().get(&longest).unwrap(), Some(1));
+        let map = HashMap::from([(format!("{}k", longest), 2)]);
+        assert!(serialize_data_to_disk(map, &path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_random_map_round_trip() {
+        let path = temp_path("kv_random");
+        let _ = fs::remove_file(&path);
+        let mut store = KvStore::open(&path).unwrap();
+        let mut expected = HashMap::new();
+        for _ in 0..generate_rand_num(1000, 2000) {
+            let key = generate_rand_string();
+            let value = generate_rand_num(1000, 20000);
+            store.put(&key, value).unwrap();
+            expected.insert(key, value);
+        }
+        drop(store);
+        assert_eq!(deserialize_data_from_disk(&path), expected);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_not_a_log() {
+        let path = temp_path("kv_not_a_log");
+        fs::write(&path, b"hello world").unwrap();
+        assert!(matches!(KvStore::open(&path), Err(KvError::BadMagic)));
+        fs::write(&path, b"SRDH\x02").unwrap();
+        assert!(matches!(KvStore::open(&path), Err(KvError::UnsupportedVersion(2))));
+        fs::write(&path, b"SRDH\x01\x07\x00\x00\x00\x00").unwrap();
+        assert!(matches!(KvStore::open(&path), Err(KvError::Corrupt(_))));
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod compaction {
+    use super::temp_path;
+    use solution::{KvOptions, KvStore};
+    use std::collections::HashMap;
+    use std::fs;
+
+    fn fill(store: &mut KvStore, expected: &mut HashMap<String, i32>, rounds: i32) {
+        for round in 0..rounds {
+            for i in 0..100 {
+                let key = format!("key{}", i);
+                if (i + round) % 5 == 0 {
+                    store.delete(&key).unwrap();
+                    expected.remove(&key);
+                } else {
+                    store.put(&key, round * 1000 + i).unwrap();
+                    expected.insert(key, round * 1000 + i);
+                }
+            }
+        }
+    }
+
+    fn check(store: &mut KvStore, expected: &HashMap<String, i32>) {
+        assert_eq!(store.len(), expected.len());
+        for i in 0..100 {
+            let key = format!("key{}", i);
+            assert_eq!(store.get(&key).unwrap(), expected.get(&key).copied(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_manual_compaction() {
+        let path = temp_path("compact_manual");
+        let _ = fs::remove_file(&path);
+        let never = KvOptions { compaction_threshold: 1.0, ..KvOptions::default() };
+        let mut store = KvStore::open_with_options(&path, never).unwrap();
+        let mut expected = HashMap::new();
+        fill(&mut store, &mut expected, 50);
+        assert_eq!(store.compactions(), 0);
+        assert!(store.dead_ratio() > 0.9);
+
+        let before = fs::metadata(&path).unwrap().len();
+        store.compact().unwrap();
+        let after = fs::metadata(&path).unwrap().len();
+        assert!(after * 10 < before, "{} -> {}", before, after);
+        assert_eq!(store.log_len(), after);
+        assert_eq!(store.dead_bytes(), 0);
+        check(&mut store, &expected);
+
+        // Writes after a compaction land in the new log and survive a reopen
+        fill(&mut store, &mut expected, 3);
+        drop(store);
+        let mut store = KvStore::open_with_options(&path, never).unwrap();
+        check(&mut store, &expected);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_automatic_compaction() {
+        let path = temp_path("compact_auto");
+        let _ = fs::remove_file(&path);
+        let options = KvOptions { compaction_threshold: 0.5, min_compaction_len: 4096 };
+        let mut store = KvStore::open_with_options(&path, options).unwrap();
+        let mut expected = HashMap::new();
+        fill(&mut store, &mut expected, 200);
+        assert!(store.compactions() > 0);
+        // The log never grows far past the threshold, whereas without compaction it would hold
+        // all 20,000 records
+        assert!(store.dead_ratio() <= 0.5);
+        assert!(fs::metadata(&path).unwrap().len() < 2 * 4096 + 100 * 20);
+        check(&mut store, &expected);
+        drop(store);
+        let mut store = KvStore::open_with_options(&path, options).unwrap();
+        check(&mut store, &expected);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_small_logs_are_left_alone() {
+        let path = temp_path("compact_small");
+        let _ = fs::remove_file(&path);
+        let mut store = KvStore::open(&path).unwrap();
+        for i in 0..100 {
+            store.put("same", i).unwrap();
+        }
+        // Almost everything is dead, but the log is far below the default minimum size
+        assert!(store.dead_ratio() > 0.9);
+        assert_eq!(store.compactions(), 0);
+        assert_eq!(store.get("same").unwrap(), Some(99));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_leftover_segment_is_ignored() {
+        let path = temp_path("compact_leftover");
+        let _ = fs::remove_file(&path);
+        let mut store = KvStore::open(&path).unwrap();
+        store.put("a", 1).unwrap();
+        drop(store);
+        // A crash before the rename leaves the new segment behind; the old log is still the truth
+        let leftover = std::path::Path::new(&path).with_file_name(format!(
+            ".{}.compact",
+            std::path::Path::new(&path).file_name().unwrap().to_string_lossy()
+        ));
+        fs::write(&leftover, b"half a segment").unwrap();
+        let mut store = KvStore::open(&path).unwrap();
+        assert_eq!(store.get("a").unwrap(), Some(1));
+        assert!(!leftover.exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_compaction_does_not_fail_the_write() {
+        let path = temp_path("compact_failed");
+        let _ = fs::remove_file(&path);
+        let options = KvOptions { compaction_threshold: 0.5, min_compaction_len: 1024 };
+        let mut store = KvStore::open_with_options(&path, options).unwrap();
+        // A directory where the new segment should go makes every compaction fail
+        let segment = std::path::Path::new(&path).with_file_name(format!(
+            ".{}.compact",
+            std::path::Path::new(&path).file_name().unwrap().to_string_lossy()
+        ));
+        fs::create_dir(&segment).unwrap();
+        for i in 0..200 {
+            store.put("same", i).unwrap();
+        }
+        assert_eq!(store.compactions(), 0);
+        assert!(store.take_compaction_error().is_some());
+        assert!(store.take_compaction_error().is_none());
+        assert_eq!(store.get("same").unwrap(), Some(199));
+
+        // The next write tries again and succeeds
+        fs::remove_dir(&segment).unwrap();
+        assert!(store.delete("same").unwrap());
+        assert_eq!(store.compactions(), 1);
+        assert!(store.take_compaction_error().is_none());
+        drop(store);
+        assert!(KvStore::open_with_options(&path, options).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod sstable {
+    use super::temp_path;
+    use solution::{KvError, SSTable, write_sstable, write_sstable_with_block_size};
+    use std::collections::{BTreeMap, HashMap};
+    use std::fs;
+
+    /// Keys "k00000" to "k09999" in steps of 3, so some keys in between are missing
+    fn sample() -> HashMap<String, i32> {
+        (0..10000).step_by(3).map(|i| (format!("k{:05}", i), i)).collect()
+    }
+
+    #[test]
+    fn test_point_lookup_reads_one_block() {
+        let path = temp_path("sstable_get");
+        let data = sample();
+        write_sstable_with_block_size(&data, &path, 256).unwrap();
+        let mut table = SSTable::open(&path).unwrap();
+        assert_eq!(table.len(), data.len() as u64);
+        assert!(table.block_count() > 100);
+        assert_eq!(table.blocks_read(), 0);
+
+        for i in [0, 3, 4998, 9999] {
+            let before = table.blocks_read();
+            assert_eq!(table.get(&format!("k{:05}", i)).unwrap(), Some(i));
+            assert_eq!(table.blocks_read() - before, 1);
+        }
+        // Absent keys read one block at most, and none when the Bloom filter rules them out
+        for key in ["k04999", "k10000", "a", ""] {
+            let before = table.blocks_read();
+            assert_eq!(table.get(key).unwrap(), None);
+            assert!(table.blocks_read() - before <= 1);
+            if !table.may_contain(key) {
+                assert_eq!(table.blocks_read(), before);
+            }
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_range_and_prefix_scans() {
+        let path = temp_path("sstable_scan");
+        let data = sample();
+        write_sstable_with_block_size(&data, &path, 128).unwrap();
+        let mut table = SSTable::open(&path).unwrap();
+        let sorted: BTreeMap<String, i32> = data.into_iter().collect();
+        let expect = |keep: &dyn Fn(&str) -> bool| -> Vec<(String, i32)> {
+            sorted.iter().filter(|(k, _)| keep(k)).map(|(k, &v)| (k.clone(), v)).collect()
+        };
+
+        assert_eq!(table.range("k01000".."k02000").unwrap(), expect(&|k| ("k01000".."k02000").contains(&k)));
+        assert_eq!(table.range("k01001"..="k01998").unwrap(), expect(&|k| ("k01001"..="k01998").contains(&k)));
+        assert_eq!(table.range(.."k00100").unwrap(), expect(&|k| k < "k00100"));
+        assert_eq!(table.range("k09990"..).unwrap(), expect(&|k| k >= "k09990"));
+        assert_eq!(table.range(..).unwrap().len(), sorted.len());
+        assert!(table.range("k5".."k4").unwrap().is_empty());
+
+        assert_eq!(table.scan_prefix("k012").unwrap(), expect(&|k| k.starts_with("k012")));
+        assert_eq!(table.scan_prefix("k").unwrap().len(), sorted.len());
+        assert!(table.scan_prefix("x").unwrap().is_empty());
+        assert!(table.scan_prefix("a").unwrap().is_empty());
+        // A narrow scan only touches the blocks around its keys
+        let before = table.blocks_read();
+        assert_eq!(table.scan_prefix("k0999").unwrap().len(), 4);
+        assert!(table.blocks_read() - before <= 2);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        let path = temp_path("sstable_edge");
+        write_sstable(&HashMap::new(), &path).unwrap();
+        let mut table = SSTable::open(&path).unwrap();
+        assert!(table.is_empty());
+        assert_eq!(table.get("anything").unwrap(), None);
+        assert!(table.range(..).unwrap().is_empty());
+
+        // Keys that sort byte-wise, including ones that are prefixes of others and non-ASCII
+        let data: HashMap<String, i32> =
+            ["", "a", "ab", "abc", "b", "é", "🦀"].iter().enumerate().map(|(i, k)| (k.to_string(), i as i32)).collect();
+        write_sstable_with_block_size(&data, &path, 1).unwrap();
+        let mut table = SSTable::open(&path).unwrap();
+        assert_eq!(table.block_count(), data.len());
+        for (key, value) in &data {
+            assert_eq!(table.get(key).unwrap(), Some(*value));
+        }
+        let keys: Vec<String> = table.scan_prefix("a").unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["a", "ab", "abc"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_table() {
+        let path = temp_path("sstable_corrupt");
+        write_sstable_with_block_size(&sample(), &path, 256).unwrap();
+        let bytes = fs::read(&path).unwrap();
+
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(SSTable::open(&path), Err(KvError::Corrupt(_))));
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        fs::write(&path, &bad_magic).unwrap();
+        assert!(matches!(SSTable::open(&path), Err(KvError::BadMagic)));
+        // Point the first index entry at the wrong offset
+        let index_offset = u64::from_le_bytes(bytes[bytes.len() - 24..bytes.len() - 16].try_into().unwrap()) as usize;
+        let key_len = u32::from_le_bytes(bytes[index_offset..index_offset + 4].try_into().unwrap()) as usize;
+        let mut bad_index = bytes.clone();
+        bad_index[index_offset + 4 + key_len] ^= 1;
+        fs::write(&path, &bad_index).unwrap();
+        assert!(matches!(SSTable::open(&path), Err(KvError::Corrupt(_))));
+        fs::write(&path, b"SRDT").unwrap();
+        assert!(matches!(SSTable::open(&path), Err(KvError::BadMagic)));
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod bloom {
+    use super::temp_path;
+    use solution::{BloomFilter, KvError, SSTable, SSTableOptions, write_sstable_with_options};
+    use std::collections::HashMap;
+    use std::fs;
+
+    /// Fraction of `probes` keys that were never inserted but pass the filter
+    fn observed_rate(filter: &BloomFilter, probes: usize) -> f64 {
+        let passed = (0..probes).filter(|i| filter.may_contain(&format!("absent{}", i))).count();
+        passed as f64 / probes as f64
+    }
+
+    #[test]
+    fn test_false_positive_rate_meets_target() {
+        for target in [0.1, 0.01, 0.001] {
+            let mut filter = BloomFilter::with_rate(20000, target);
+            for i in 0..20000 {
+                filter.insert(&format!("present{}", i));
+            }
+            // Never a false negative
+            assert!((0..20000).all(|i| filter.may_contain(&format!("present{}", i))));
+            let observed = observed_rate(&filter, 200000);
+            println!("target {:.3}: observed {:.4} with {} bits, {} hashes", target, observed, filter.bits(), filter.hashes());
+            assert!(observed < target * 1.5, "target {} observed {}", target, observed);
+        }
+    }
+
+    #[test]
+    fn test_table_skips_blocks_for_absent_keys() {
+        let path = temp_path("bloom_table");
+        let data: HashMap<String, i32> = (0..10000).map(|i| (format!("present{}", i), i)).collect();
+        let options = SSTableOptions { block_size: 512, false_positive_rate: 0.01 };
+        write_sstable_with_options(&data, &path, &options).unwrap();
+        let mut table = SSTable::open(&path).unwrap();
+
+        // Absent keys that sort between present ones, so the index alone cannot rule them out
+        let probes: Vec<String> = (0..10000).map(|i| format!("present{}x", i)).collect();
+        let false_positives = probes.iter().filter(|key| table.may_contain(key)).count();
+        for key in &probes {
+            assert_eq!(table.get(key).unwrap(), None);
+        }
+        // Only the filter's false positives reach a data block
+        println!("absent lookups that read a block: {} of {}", table.blocks_read(), probes.len());
+        assert_eq!(table.blocks_read(), false_positives as u64);
+        assert!(false_positives < 200);
+
+        let before = table.blocks_read();
+        for (key, value) in data.iter().take(100) {
+            assert_eq!(table.get(key).unwrap(), Some(*value));
+        }
+        assert_eq!(table.blocks_read() - before, 100);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_filter_round_trip_and_corruption() {
+        let mut filter = BloomFilter::with_rate(100, 0.01);
+        filter.insert("a");
+        filter.insert("b");
+        let mut bytes = Vec::new();
+        filter.encode(&mut bytes);
+        assert_eq!(BloomFilter::decode(&bytes).unwrap(), filter);
+        assert!(matches!(BloomFilter::decode(&bytes[..bytes.len() - 1]), Err(KvError::Corrupt(_))));
+        assert!(matches!(BloomFilter::decode(&bytes[..5]), Err(KvError::Corrupt(_))));
+        // An empty filter still answers, and rules out everything
+        let empty = BloomFilter::with_rate(0, 0.01);
+        assert!(!empty.may_contain("anything"));
+    }
+}
+
+mod wal {
+    use super::temp_path;
+    use solution::{DurableMap, KvError, SyncPolicy, wal_path_for, deserialize_data_from_disk};
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::rc::Rc;
+
+    /// A write to replay against a plain HashMap
+    #[derive(Debug, Clone)]
+    enum Write {
+        Put(String, i32),
+        Delete(String),
+    }
+
+    fn writes(count: i32) -> Vec<Write> {
+        (0..count)
+            .map(|i| match i % 4 {
+                3 => Write::Delete(format!("key{}", (i * 7) % 10)),
+                _ => Write::Put(format!("key{}", (i * 7) % 10), i),
+            })
+            .collect()
+    }
+
+    fn apply(map: &mut DurableMap, write: &Write) {
+        match write {
+            Write::Put(key, value) => map.put(key, *value).unwrap(),
+            Write::Delete(key) => {
+                map.delete(key).unwrap();
+            }
+        }
+    }
+
+    /// The map after each prefix of `writes`, starting from `base`: states[k] is after k writes
+    fn states(base: &HashMap<String, i32>, writes: &[Write]) -> Vec<HashMap<String, i32>> {
+        let mut map = base.clone();
+        let mut states = vec![map.clone()];
+        for write in writes {
+            match write {
+                Write::Put(key, value) => map.insert(key.clone(), *value),
+                Write::Delete(key) => map.remove(key),
+            };
+            states.push(map.clone());
+        }
+        states
+    }
+
+    fn remove(path: &str) {
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_file(wal_path_for(path));
+    }
+
+    #[test]
+    fn test_recovery_replays_log() {
+        let path = temp_path("wal_replay");
+        remove(&path);
+        let writes = writes(40);
+        {
+            let mut map = DurableMap::open(&path, SyncPolicy::Always).unwrap();
+            for write in &writes {
+                apply(&mut map, write);
+            }
+        }
+        let expected = states(&HashMap::new(), &writes).pop().unwrap();
+        let map = DurableMap::open(&path, SyncPolicy::Always).unwrap();
+        assert_eq!(map.as_map(), &expected);
+        // No snapshot has been written yet; everything came from the log
+        assert!(!std::path::Path::new(&path).exists());
+        remove(&path);
+    }
+
+    #[test]
+    fn test_checkpoint_then_log() {
+        let path = temp_path("wal_checkpoint");
+        remove(&path);
+        let before = writes(30);
+        let after: Vec<Write> = writes(60).split_off(30);
+        {
+            let mut map = DurableMap::open(&path, SyncPolicy::Always).unwrap();
+            before.iter().for_each(|w| apply(&mut map, w));
+            map.checkpoint().unwrap();
+            assert_eq!(fs::metadata(wal_path_for(&path)).unwrap().len(), 5);
+            after.iter().for_each(|w| apply(&mut map, w));
+        }
+        let snapshot = states(&HashMap::new(), &before).pop().unwrap();
+        assert_eq!(deserialize_data_from_disk(&path), snapshot);
+        let map = DurableMap::open(&path, SyncPolicy::Always).unwrap();
+        assert_eq!(map.as_map(), &states(&snapshot, &after).pop().unwrap());
+        remove(&path);
+    }
+
+    /// Cuts the log after every byte and checks that recovery gives the state after exactly the
+    /// writes whose frames are complete, which is always a prefix of the acknowledged writes
+    fn cut_everywhere(name: &str, checkpoint_after: Option<usize>) {
+        let path = temp_path(name);
+        remove(&path);
+        let writes = writes(24);
+        let mut frame_ends = Vec::new();
+        {
+            let mut map = DurableMap::open(&path, SyncPolicy::Always).unwrap();
+            for (i, write) in writes.iter().enumerate() {
+                if checkpoint_after == Some(i) {
+                    map.checkpoint().unwrap();
+                    frame_ends.clear();
+                }
+                apply(&mut map, write);
+                let end = fs::metadata(wal_path_for(&path)).unwrap().len() as usize;
+                // Deletes of missing keys log nothing
+                if frame_ends.last() != Some(&end) {
+                    frame_ends.push(end);
+                }
+            }
+        }
+        let logged = checkpoint_after.unwrap_or(0);
+        let snapshot = states(&HashMap::new(), &writes[..logged]).pop().unwrap();
+        let expected = states(&snapshot, &writes[logged..]);
+        let snapshot_bytes = fs::read(&path).ok();
+        let log = fs::read(wal_path_for(&path)).unwrap();
+
+        for cut in 0..=log.len() {
+            match &snapshot_bytes {
+                Some(bytes) => fs::write(&path, bytes).unwrap(),
+                None => remove(&path),
+            }
+            fs::write(wal_path_for(&path), &log[..cut]).unwrap();
+            let mut map = DurableMap::open(&path, SyncPolicy::Always).unwrap();
+            let complete = frame_ends.iter().filter(|&&end| end <= cut).count();
+            let acknowledged = expected.iter().position(|state| state == map.as_map());
+            assert!(acknowledged.is_some(), "cut at {} recovered a state that never existed", cut);
+            assert_eq!(map.as_map(), &states_after_frames(&snapshot, &writes[logged..], complete), "cut at {}", cut);
+
+            // The torn frame is gone, so new writes are recovered too
+            map.put("after-crash", cut as i32).unwrap();
+            drop(map);
+            assert_eq!(DurableMap::open(&path, SyncPolicy::Always).unwrap().get("after-crash"), Some(cut as i32));
+        }
+        remove(&path);
+    }
+
+    /// The state after the writes that produced the first `frames` log frames
+    fn states_after_frames(base: &HashMap<String, i32>, writes: &[Write], frames: usize) -> HashMap<String, i32> {
+        let mut map = base.clone();
+        let mut logged = 0;
+        for write in writes {
+            if logged == frames {
+                break;
+            }
+            match write {
+                Write::Put(key, value) => {
+                    map.insert(key.clone(), *value);
+                    logged += 1;
+                }
+                Write::Delete(key) => {
+                    if map.remove(key).is_some() {
+                        logged += 1;
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_cut_log_at_every_offset() {
+        cut_everywhere("wal_cut", None);
+    }
+
+    #[test]
+    fn test_cut_log_after_checkpoint_at_every_offset() {
+        cut_everywhere("wal_cut_checkpoint", Some(10));
+    }
+
+    #[test]
+    fn test_corrupt_last_frame_is_dropped() {
+        let path = temp_path("wal_flip");
+        remove(&path);
+        {
+            let mut map = DurableMap::open(&path, SyncPolicy::Always).unwrap();
+            map.put("a", 1).unwrap();
+            map.put("b", 2).unwrap();
+        }
+        // Flip one bit in the last byte, the value of "b"
+        let mut log = fs::read(wal_path_for(&path)).unwrap();
+        *log.last_mut().unwrap() ^= 0x10;
+        fs::write(wal_path_for(&path), &log).unwrap();
+        let map = DurableMap::open(&path, SyncPolicy::Always).unwrap();
+        assert_eq!(map.get("a"), Some(1));
+        assert_eq!(map.get("b"), None);
+
+        fs::write(wal_path_for(&path), b"not a log").unwrap();
+        assert!(matches!(DurableMap::open(&path, SyncPolicy::Always), Err(KvError::BadMagic)));
+        remove(&path);
+    }
+
+    #[test]
+    fn test_sync_policies() {
+        let path = temp_path("wal_policy");
+        for (policy, syncs) in [(SyncPolicy::Always, 10), (SyncPolicy::EveryN(3), 3), (SyncPolicy::Never, 0)] {
+            remove(&path);
+            let mut map = DurableMap::open(&path, policy).unwrap();
+            for i in 0..10 {
+                map.put("key", i).unwrap();
+            }
+            assert_eq!(map.syncs(), syncs, "{:?}", policy);
+            map.sync().unwrap();
+            assert_eq!(map.syncs(), syncs + 1);
+            drop(map);
+            assert_eq!(DurableMap::open(&path, policy).unwrap().get("key"), Some(9));
+        }
+        remove(&path);
+    }
+
+    #[test]
+    fn test_failed_sync_is_rolled_back() {
+        let path = temp_path("wal_failed_sync");
+        for policy in [SyncPolicy::Always, SyncPolicy::EveryN(3)] {
+            remove(&path);
+            let failing = Rc::new(Cell::new(false));
+            let hook_failing = Rc::clone(&failing);
+            let mut map = DurableMap::open(&path, policy).unwrap().with_sync_hook(move || match hook_failing.get() {
+                true => Err(std::io::Error::other("simulated fsync failure")),
+                false => Ok(()),
+            });
+            map.put("a", 1).unwrap();
+            map.put("b", 2).unwrap();
+            let wal_len = fs::metadata(wal_path_for(&path)).unwrap().len();
+
+            // The third write is the one that syncs under both policies
+            failing.set(true);
+            assert!(matches!(map.put("c", 3), Err(KvError::Io(_))));
+            assert_eq!(map.get("c"), None);
+            assert_eq!(fs::metadata(wal_path_for(&path)).unwrap().len(), wal_len, "{:?}", policy);
+
+            failing.set(false);
+            map.put("d", 4).unwrap();
+            let live = map.as_map().clone();
+            drop(map);
+            let recovered = DurableMap::open(&path, policy).unwrap();
+            assert_eq!(recovered.as_map(), &live, "{:?}", policy);
+            assert_eq!(live, HashMap::from([("a".to_string(), 1), ("b".to_string(), 2), ("d".to_string(), 4)]));
+        }
+        remove(&path);
+    }
+}
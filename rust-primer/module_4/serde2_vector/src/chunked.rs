@@ -0,0 +1,204 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::compress::{BLOCK_HEADER_LEN, BLOCK_LEN};
+use crate::encoding::{choose_encoding, decode_body, encode_body};
+use crate::format::{Encoding, VectorError};
+
+// The chunked encoding cuts the vector into chunks of `chunk len` elements (the last one may be
+// shorter) and encodes each on its own, in whichever encoding suits it best. Since no chunk
+// depends on another, worker threads can encode and decode them in any order. A table after the
+// chunk sizes says where every chunk starts, so a decoder can hand out chunks without reading
+// them first:
+//
+//   chunk len     u64
+//   chunk count   u64                       ceil(count / chunk len)
+//   offsets       (chunk count + 1) * u64   chunk i is at offsets[i]..offsets[i + 1], counted
+//                                           from the end of the encodings; offsets[0] is 0
+//   encodings     chunk count * u8          encoding code of each chunk
+//   chunks
+//
+// All fields are little-endian, and so are the elements of plain chunks.
+
+/// How `encode_chunked` splits the vector and how many threads it uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    /// Elements per chunk
+    pub chunk_len: usize,
+    /// Worker threads; 1 encodes on the calling thread
+    pub threads: usize,
+}
+
+impl Default for ChunkOptions {
+    /// Chunks of a million elements, one thread per CPU
+    fn default() -> Self {
+        ChunkOptions { chunk_len: 1 << 20, threads: default_threads() }
+    }
+}
+
+/// One worker per CPU, or a single one if that cannot be determined
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+fn encode_chunk(chunk: &[i32]) -> (Encoding, Vec<u8>) {
+    match choose_encoding(chunk) {
+        Encoding::Plain => (Encoding::Plain, chunk.iter().flat_map(|v| v.to_le_bytes()).collect()),
+        other => (other, encode_body(chunk, other)),
+    }
+}
+
+fn decode_chunk(bytes: &[u8], encoding: Encoding, out: &mut [i32]) -> Result<(), VectorError> {
+    match encoding {
+        Encoding::Plain => {
+            if bytes.len() != 4 * out.len() {
+                return Err(VectorError::Corrupt("plain chunk has the wrong length"));
+            }
+            for (value, chunk) in out.iter_mut().zip(bytes.chunks_exact(4)) {
+                *value = i32::from_le_bytes(chunk.try_into().unwrap());
+            }
+        }
+        Encoding::Chunked => return Err(VectorError::Corrupt("chunk is itself chunked")),
+        other => out.copy_from_slice(&decode_body(bytes, other, out.len() as u64)?),
+    }
+    Ok(())
+}
+
+/// The most elements `bytes` could hold in `encoding`, so a chunk can be checked against the
+/// element count before any memory is set aside for it
+fn max_chunk_len(bytes: &[u8], encoding: Encoding) -> u64 {
+    let len = bytes.len() as u64;
+    match encoding {
+        Encoding::Plain | Encoding::ZoneMapped => len / 4,
+        Encoding::DeltaBitPacked => len / BLOCK_HEADER_LEN as u64 * BLOCK_LEN as u64,
+        Encoding::RunLength => len / 8 * u32::MAX as u64,
+        // A single value needs no index bits at all, so only a larger dictionary bounds the count
+        Encoding::Dictionary => match bytes.get(..4).map(|b| u32::from_le_bytes(b.try_into().unwrap())) {
+            Some(0) | None => 0,
+            Some(1) => u64::MAX,
+            Some(_) => len.saturating_mul(8),
+        },
+        Encoding::Chunked => 0,
+    }
+}
+
+/// Runs `task(i)` for every `i` in `0..tasks` on up to `threads` threads, returning the results
+/// in order of `i`. Workers take the next task as soon as they finish one, so uneven tasks still
+/// keep every thread busy.
+fn run_parallel<T, F>(tasks: usize, threads: usize, task: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    let threads = threads.clamp(1, tasks.max(1));
+    if threads == 1 {
+        return (0..tasks).map(task).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(tasks));
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= tasks {
+                    break;
+                }
+                let result = task(i);
+                results.lock().unwrap().push((i, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_unstable_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Encodes `data` as independently encoded chunks, using `options.threads` threads
+pub fn encode_chunked(data: &[i32], options: &ChunkOptions) -> Vec<u8> {
+    assert!(options.chunk_len > 0, "chunks must hold at least one element");
+    let chunks: Vec<&[i32]> = data.chunks(options.chunk_len).collect();
+    let encoded = run_parallel(chunks.len(), options.threads, |i| encode_chunk(chunks[i]));
+
+    let body_len: usize = encoded.iter().map(|(_, bytes)| bytes.len()).sum();
+    let mut out = Vec::with_capacity(16 + 9 * encoded.len() + 8 + body_len);
+    out.extend_from_slice(&(options.chunk_len as u64).to_le_bytes());
+    out.extend_from_slice(&(encoded.len() as u64).to_le_bytes());
+    let mut offset = 0u64;
+    out.extend_from_slice(&offset.to_le_bytes());
+    for (_, bytes) in &encoded {
+        offset += bytes.len() as u64;
+        out.extend_from_slice(&offset.to_le_bytes());
+    }
+    out.extend(encoded.iter().map(|(encoding, _)| encoding.code()));
+    for (_, bytes) in &encoded {
+        out.extend_from_slice(bytes);
+    }
+    out
+}
+
+/// Decodes chunks holding exactly `count` elements, using `threads` threads. The result does not
+/// depend on the number of threads, and neither does the error if several chunks are corrupt:
+/// it is always the one from the first bad chunk.
+pub fn decode_chunked(bytes: &[u8], count: u64, threads: usize) -> Result<Vec<i32>, VectorError> {
+    let truncated = |expected: u64| VectorError::Truncated { expected, found: bytes.len() as u64 };
+    let read_u64 = |at: usize| bytes.get(at..at + 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+
+    let chunk_len = read_u64(0).ok_or(truncated(16))?;
+    let chunk_count = read_u64(8).ok_or(truncated(16))?;
+    let expected_chunks = match (count, chunk_len) {
+        (0, _) => 0,
+        (_, 0) => return Err(VectorError::Corrupt("chunks hold no elements")),
+        _ => count.div_ceil(chunk_len),
+    };
+    if chunk_count != expected_chunks {
+        return Err(VectorError::Corrupt("chunk count does not match the element count"));
+    }
+    // Every chunk has a table entry, so a chunk count the input cannot hold means it is cut short
+    let table_len = chunk_count
+        .checked_mul(9)
+        .and_then(|len| len.checked_add(24))
+        .filter(|&len| len <= bytes.len() as u64)
+        .ok_or(truncated(chunk_count.saturating_mul(9).saturating_add(24)))? as usize;
+    let chunk_count = chunk_count as usize;
+    let offsets: Vec<u64> = (0..=chunk_count).map(|i| read_u64(16 + 8 * i).unwrap()).collect();
+    let encodings = bytes[16 + 8 * (chunk_count + 1)..table_len]
+        .iter()
+        .map(|&code| Encoding::from_code(code))
+        .collect::<Result<Vec<Encoding>, VectorError>>()?;
+
+    let chunks = &bytes[table_len..];
+    if offsets[0] != 0 || offsets.windows(2).any(|w| w[0] > w[1]) {
+        return Err(VectorError::Corrupt("chunk offsets go backwards"));
+    }
+    let end = offsets[chunk_count];
+    if end > chunks.len() as u64 {
+        return Err(truncated(table_len as u64 + end));
+    }
+    if end != chunks.len() as u64 {
+        return Err(VectorError::Corrupt("unexpected bytes after the last chunk"));
+    }
+
+    // A corrupt count could ask for more memory than there is, or than the chunks can hold; fail
+    // instead of aborting or filling gigabytes of zeroes
+    for i in 0..chunk_count {
+        let elements = chunk_len.min(count - i as u64 * chunk_len);
+        if max_chunk_len(&chunks[offsets[i] as usize..offsets[i + 1] as usize], encodings[i]) < elements {
+            return Err(VectorError::Corrupt("chunk too short for its elements"));
+        }
+    }
+    let too_large = || VectorError::Corrupt("element count too large");
+    let mut out = Vec::new();
+    out.try_reserve_exact(usize::try_from(count).map_err(|_| too_large())?).map_err(|_| too_large())?;
+    out.resize(count as usize, 0);
+    {
+        // Every worker writes into its own chunk of the output
+        let outputs: Vec<Mutex<&mut [i32]>> = out.chunks_mut(chunk_len.max(1) as usize).map(Mutex::new).collect();
+        let results = run_parallel(chunk_count, threads, |i| {
+            let chunk = &chunks[offsets[i] as usize..offsets[i + 1] as usize];
+            decode_chunk(chunk, encodings[i], &mut outputs[i].lock().unwrap())
+        });
+        results.into_iter().collect::<Result<(), VectorError>>()?;
+    }
+    Ok(out)
+}
@@ -0,0 +1,174 @@
+use crate::format::VectorError;
+
+// Delta + frame-of-reference + bit-packing, one block of up to BLOCK_LEN elements at a time.
+//
+// Within a block we keep the first value as is and replace every other value by its difference
+// from the previous one. Subtracting the smallest difference (the "frame of reference") makes all
+// differences non-negative, and then each one only needs as many bits as the largest. For a
+// counter every difference is 1, so after subtracting the reference they are all 0 and need no
+// bits at all: a block of 128 elements shrinks to its header.
+//
+// Block layout (all fields little-endian):
+//
+//   count       u16   number of elements in the block (1..=BLOCK_LEN)
+//   bit width   u8    bits per packed difference (0..=33)
+//   first       i32   first element of the block
+//   reference   i64   smallest difference in the block
+//   packed      ceil((count - 1) * bit width / 8) bytes, least significant bit first
+//
+// Each block says how many elements and bits it holds, so it can be decoded (or skipped)
+// without looking at any other block.
+
+/// Maximum number of elements per block
+pub const BLOCK_LEN: usize = 128;
+
+/// Size of the per-block header in bytes
+pub const BLOCK_HEADER_LEN: usize = 2 + 1 + 4 + 8;
+
+/// Differences between two i32s span 33 bits
+const MAX_BIT_WIDTH: u8 = 33;
+
+/// Encodes `data` as a sequence of delta bit-packed blocks
+pub fn encode_delta_blocks(data: &[i32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for block in data.chunks(BLOCK_LEN) {
+        encode_block(block, &mut out);
+    }
+    out
+}
+
+fn encode_block(block: &[i32], out: &mut Vec<u8>) {
+    let deltas: Vec<i64> = block.windows(2).map(|w| w[1] as i64 - w[0] as i64).collect();
+    let reference = deltas.iter().copied().min().unwrap_or(0);
+    let max_offset = deltas.iter().map(|&d| (d - reference) as u64).max().unwrap_or(0);
+    let bit_width = (64 - max_offset.leading_zeros()) as u8;
+
+    out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+    out.push(bit_width);
+    out.extend_from_slice(&block[0].to_le_bytes());
+    out.extend_from_slice(&reference.to_le_bytes());
+
+    let mut packer = BitPacker::new(out, bit_width);
+    for &delta in &deltas {
+        packer.push((delta - reference) as u64);
+    }
+    packer.finish();
+}
+
+/// Decodes the block at the start of `bytes`, appending its elements to `out`.
+/// Returns the number of bytes the block occupied.
+pub fn decode_block(bytes: &[u8], out: &mut Vec<i32>) -> Result<usize, VectorError> {
+    let header = bytes.get(..BLOCK_HEADER_LEN).ok_or(VectorError::Truncated {
+        expected: BLOCK_HEADER_LEN as u64,
+        found: bytes.len() as u64,
+    })?;
+    let count = u16::from_le_bytes([header[0], header[1]]) as usize;
+    let bit_width = header[2];
+    let first = i32::from_le_bytes(header[3..7].try_into().unwrap());
+    let reference = i64::from_le_bytes(header[7..15].try_into().unwrap());
+    if count == 0 || count > BLOCK_LEN {
+        return Err(VectorError::Corrupt("block element count out of range"));
+    }
+    if bit_width > MAX_BIT_WIDTH {
+        return Err(VectorError::Corrupt("block bit width too large"));
+    }
+
+    let packed_len = ((count - 1) * bit_width as usize).div_ceil(8);
+    let block_len = BLOCK_HEADER_LEN + packed_len;
+    let packed = bytes.get(BLOCK_HEADER_LEN..block_len).ok_or(VectorError::Truncated {
+        expected: block_len as u64,
+        found: bytes.len() as u64,
+    })?;
+
+    let mut unpacker = BitUnpacker::new(packed, bit_width);
+    // `first` and `reference` come from the file, so every step is checked for overflow
+    let out_of_range = || VectorError::Corrupt("decoded value out of range");
+    let mut value = i64::from(first);
+    out.push(first);
+    for _ in 1..count {
+        // At most MAX_BIT_WIDTH bits, so the delta itself always fits
+        let delta = unpacker.next() as i64;
+        value = value.checked_add(reference).and_then(|v| v.checked_add(delta)).ok_or_else(out_of_range)?;
+        let element = i32::try_from(value).map_err(|_| out_of_range())?;
+        out.push(element);
+    }
+    Ok(block_len)
+}
+
+/// Decodes a sequence of blocks that together must hold exactly `count` elements
+pub fn decode_delta_blocks(bytes: &[u8], count: u64) -> Result<Vec<i32>, VectorError> {
+    let mut out = Vec::with_capacity(count.min(bytes.len() as u64 * 8) as usize);
+    let mut offset = 0;
+    while (out.len() as u64) < count {
+        offset += decode_block(&bytes[offset..], &mut out)?;
+    }
+    if out.len() as u64 != count {
+        return Err(VectorError::Corrupt("blocks hold more elements than the header count"));
+    }
+    if offset != bytes.len() {
+        return Err(VectorError::Corrupt("unexpected bytes after the last block"));
+    }
+    Ok(out)
+}
+
+/// Writes fixed-width values into a byte vector, least significant bit first
+pub(crate) struct BitPacker<'a> {
+    out: &'a mut Vec<u8>,
+    bit_width: u8,
+    buffer: u64,
+    buffered_bits: u8,
+}
+
+impl<'a> BitPacker<'a> {
+    pub(crate) fn new(out: &'a mut Vec<u8>, bit_width: u8) -> Self {
+        BitPacker { out, bit_width, buffer: 0, buffered_bits: 0 }
+    }
+
+    pub(crate) fn push(&mut self, value: u64) {
+        if self.bit_width == 0 {
+            return;
+        }
+        // At most 7 bits are left over from before, and 7 + 33 fits comfortably in a u64
+        self.buffer |= value << self.buffered_bits;
+        self.buffered_bits += self.bit_width;
+        while self.buffered_bits >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.buffered_bits -= 8;
+        }
+    }
+
+    pub(crate) fn finish(self) {
+        if self.buffered_bits > 0 {
+            self.out.push(self.buffer as u8);
+        }
+    }
+}
+
+/// Reads back the values written by a `BitPacker`
+pub(crate) struct BitUnpacker<'a> {
+    bytes: &'a [u8],
+    bit_width: u8,
+    position: usize,
+    buffer: u64,
+    buffered_bits: u8,
+}
+
+impl<'a> BitUnpacker<'a> {
+    pub(crate) fn new(bytes: &'a [u8], bit_width: u8) -> Self {
+        BitUnpacker { bytes, bit_width, position: 0, buffer: 0, buffered_bits: 0 }
+    }
+
+    /// The caller checked that `bytes` holds enough bits for every value it asks for
+    pub(crate) fn next(&mut self) -> u64 {
+        while self.buffered_bits < self.bit_width {
+            self.buffer |= (self.bytes[self.position] as u64) << self.buffered_bits;
+            self.position += 1;
+            self.buffered_bits += 8;
+        }
+        let value = self.buffer & ((1u64 << self.bit_width) - 1);
+        self.buffer >>= self.bit_width;
+        self.buffered_bits -= self.bit_width;
+        value
+    }
+}
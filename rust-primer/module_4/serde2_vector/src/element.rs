@@ -0,0 +1,90 @@
+use crate::encoding::decode_body;
+use crate::format::{ElementType, Encoding, Endianness, VectorError};
+
+// Plain files store fixed-width elements back to back in the byte order given by the header,
+// except bools, which are packed eight to a byte (least significant bit first, unused bits of the
+// last byte zero) and have no byte order. Floats are stored as their IEEE-754 bits, so every
+// value, NaN payloads included, reads back exactly.
+
+/// A type that can be stored in a vector file
+pub trait Element: Copy + Sized {
+    /// The type code written to the header
+    const TYPE: ElementType;
+
+    /// Appends the plain encoding of `data` to `out`
+    fn encode_plain(data: &[Self], endianness: Endianness, out: &mut Vec<u8>);
+
+    /// Decodes `count` elements from `bytes`, which hold exactly their plain encoding
+    fn decode_plain(bytes: &[u8], count: usize, endianness: Endianness) -> Result<Vec<Self>, VectorError>;
+
+    /// Decodes elements stored in an encoding other than `Plain`. Only i32 vectors can be
+    /// compressed, so by default this refuses.
+    fn decode_encoded(_bytes: &[u8], encoding: Encoding, _count: u64) -> Result<Vec<Self>, VectorError> {
+        Err(VectorError::EncodingNotSupported(encoding))
+    }
+}
+
+macro_rules! impl_element {
+    ($($t:ty => $code:ident),*) => {$(
+        impl Element for $t {
+            const TYPE: ElementType = ElementType::$code;
+
+            fn encode_plain(data: &[Self], endianness: Endianness, out: &mut Vec<u8>) {
+                for value in data {
+                    match endianness {
+                        Endianness::Little => out.extend_from_slice(&value.to_le_bytes()),
+                        Endianness::Big => out.extend_from_slice(&value.to_be_bytes()),
+                    }
+                }
+            }
+
+            fn decode_plain(bytes: &[u8], _count: usize, endianness: Endianness) -> Result<Vec<Self>, VectorError> {
+                Ok(bytes
+                    .chunks_exact(std::mem::size_of::<$t>())
+                    .map(|chunk| match endianness {
+                        Endianness::Little => <$t>::from_le_bytes(chunk.try_into().unwrap()),
+                        Endianness::Big => <$t>::from_be_bytes(chunk.try_into().unwrap()),
+                    })
+                    .collect())
+            }
+        }
+    )*};
+}
+
+impl_element!(u8 => U8, u16 => U16, u32 => U32, u64 => U64, i64 => I64, f32 => F32, f64 => F64);
+
+impl Element for i32 {
+    const TYPE: ElementType = ElementType::I32;
+
+    fn encode_plain(data: &[Self], endianness: Endianness, out: &mut Vec<u8>) {
+        for &value in data {
+            out.extend_from_slice(&endianness.write_i32(value));
+        }
+    }
+
+    fn decode_plain(bytes: &[u8], _count: usize, endianness: Endianness) -> Result<Vec<Self>, VectorError> {
+        Ok(endianness.read_i32s(bytes))
+    }
+
+    fn decode_encoded(bytes: &[u8], encoding: Encoding, count: u64) -> Result<Vec<Self>, VectorError> {
+        decode_body(bytes, encoding, count)
+    }
+}
+
+impl Element for bool {
+    const TYPE: ElementType = ElementType::Bool;
+
+    fn encode_plain(data: &[Self], _endianness: Endianness, out: &mut Vec<u8>) {
+        for chunk in data.chunks(8) {
+            out.push(chunk.iter().enumerate().fold(0u8, |byte, (i, &bit)| byte | ((bit as u8) << i)));
+        }
+    }
+
+    fn decode_plain(bytes: &[u8], count: usize, _endianness: Endianness) -> Result<Vec<Self>, VectorError> {
+        let unused_bits = bytes.len() * 8 - count;
+        if unused_bits > 0 && bytes[bytes.len() - 1] >> (8 - unused_bits) != 0 {
+            return Err(VectorError::Corrupt("bits set after the last bool"));
+        }
+        Ok((0..count).map(|i| bytes[i / 8] >> (i % 8) & 1 == 1).collect())
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use crate::chunked::{encode_chunked, decode_chunked, default_threads, ChunkOptions};
+use crate::compress::{BitPacker, BitUnpacker, encode_delta_blocks, decode_delta_blocks};
+use crate::format::{Encoding, VectorError};
+use crate::zone_map::{encode_zone_blocks, decode_zone_blocks};
+
+// Two encodings for vectors with few distinct values, such as status codes:
+//
+// Run-length: consecutive repeats collapse into (value i32, run length u32) pairs, little-endian.
+// Ideal when equal values come in long stretches.
+//
+// Dictionary: every distinct value is stored once, sorted, and each element becomes the
+// bit-packed index of its value:
+//
+//   dictionary length   u32
+//   dictionary          dictionary length * i32
+//   bit width           u8    bits per index, enough for dictionary length - 1
+//   indices             ceil(count * bit width / 8) bytes, least significant bit first
+//
+// Ideal when there are few distinct values but they are mixed together.
+
+/// Encodes `data` as (value, run length) pairs
+pub fn encode_rle(data: &[i32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut rest = data;
+    while let Some(&value) = rest.first() {
+        let run = rest.iter().take_while(|&&v| v == value).count().min(u32::MAX as usize);
+        out.extend_from_slice(&value.to_le_bytes());
+        out.extend_from_slice(&(run as u32).to_le_bytes());
+        rest = &rest[run..];
+    }
+    out
+}
+
+/// Decodes run-length pairs that together must hold exactly `count` elements
+pub fn decode_rle(bytes: &[u8], count: u64) -> Result<Vec<i32>, VectorError> {
+    if !bytes.len().is_multiple_of(8) {
+        return Err(VectorError::Corrupt("run-length data is not a whole number of runs"));
+    }
+    let runs = || {
+        bytes.chunks_exact(8).map(|pair| {
+            let value = i32::from_le_bytes(pair[0..4].try_into().unwrap());
+            (value, u32::from_le_bytes(pair[4..8].try_into().unwrap()) as u64)
+        })
+    };
+    // Add up the runs before allocating anything, so a corrupt count or run cannot ask for more
+    // elements than the runs really hold
+    let mut total = 0u64;
+    for (_, run) in runs() {
+        if run == 0 {
+            return Err(VectorError::Corrupt("empty run"));
+        }
+        total += run;
+        if total > count {
+            return Err(VectorError::Corrupt("runs hold more elements than the header count"));
+        }
+    }
+    if total < count {
+        return Err(VectorError::Truncated { expected: count, found: total });
+    }
+    let mut out = allocate(count)?;
+    for (value, run) in runs() {
+        out.extend(std::iter::repeat_n(value, run as usize));
+    }
+    Ok(out)
+}
+
+/// An empty vector with room for `count` elements. A corrupt count could ask for more memory than
+/// there is; fail instead of aborting.
+fn allocate(count: u64) -> Result<Vec<i32>, VectorError> {
+    let too_large = || VectorError::Corrupt("element count too large");
+    let mut out = Vec::new();
+    out.try_reserve_exact(usize::try_from(count).map_err(|_| too_large())?).map_err(|_| too_large())?;
+    Ok(out)
+}
+
+/// Bits needed to tell `n` different indices apart
+fn index_bit_width(n: usize) -> u8 {
+    (usize::BITS - n.saturating_sub(1).leading_zeros()) as u8
+}
+
+/// Encodes `data` as a sorted dictionary of its distinct values plus bit-packed indices
+pub fn encode_dictionary(data: &[i32]) -> Vec<u8> {
+    let mut dictionary: Vec<i32> = data.to_vec();
+    dictionary.sort_unstable();
+    dictionary.dedup();
+    let index: HashMap<i32, u64> = dictionary.iter().enumerate().map(|(i, &v)| (v, i as u64)).collect();
+    let bit_width = index_bit_width(dictionary.len());
+
+    let mut out = Vec::with_capacity(4 + 4 * dictionary.len() + 1 + (data.len() * bit_width as usize).div_ceil(8));
+    out.extend_from_slice(&(dictionary.len() as u32).to_le_bytes());
+    for value in &dictionary {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.push(bit_width);
+    let mut packer = BitPacker::new(&mut out, bit_width);
+    for value in data {
+        packer.push(index[value]);
+    }
+    packer.finish();
+    out
+}
+
+/// Decodes a dictionary-encoded vector of `count` elements
+pub fn decode_dictionary(bytes: &[u8], count: u64) -> Result<Vec<i32>, VectorError> {
+    let truncated = |expected: usize| VectorError::Truncated { expected: expected as u64, found: bytes.len() as u64 };
+    let len_bytes = bytes.get(..4).ok_or(truncated(4))?;
+    let dictionary_len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+    if dictionary_len as u64 > count || (count > 0 && dictionary_len == 0) {
+        return Err(VectorError::Corrupt("dictionary size does not fit the element count"));
+    }
+    let dictionary_end = 4 + 4 * dictionary_len;
+    let dictionary: Vec<i32> = bytes.get(4..dictionary_end).ok_or(truncated(dictionary_end))?
+        .chunks_exact(4)
+        .map(|chunk| i32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+
+    let bit_width = *bytes.get(dictionary_end).ok_or(truncated(dictionary_end + 1))?;
+    if bit_width != index_bit_width(dictionary_len) {
+        return Err(VectorError::Corrupt("index bit width does not match the dictionary size"));
+    }
+    let packed_len = usize::try_from(count)
+        .ok()
+        .and_then(|count| count.checked_mul(bit_width as usize))
+        .map(|bits| bits.div_ceil(8))
+        .ok_or(VectorError::Corrupt("element count too large"))?;
+    let end = dictionary_end + 1 + packed_len;
+    let packed = bytes.get(dictionary_end + 1..end).ok_or(truncated(end))?;
+    if end != bytes.len() {
+        return Err(VectorError::Corrupt("unexpected bytes after the indices"));
+    }
+
+    // With a single value the indices take no bytes at all, so the input does not bound the count
+    let mut out = allocate(count)?;
+    let mut unpacker = BitUnpacker::new(packed, bit_width);
+    for _ in 0..count {
+        let value = dictionary.get(unpacker.next() as usize).ok_or(VectorError::Corrupt("index outside the dictionary"))?;
+        out.push(*value);
+    }
+    Ok(out)
+}
+
+/// Encodes `data` with any encoding but `Plain` (whose bytes depend on the endianness)
+pub(crate) fn encode_body(data: &[i32], encoding: Encoding) -> Vec<u8> {
+    match encoding {
+        Encoding::Plain => unreachable!("plain data is written by the caller"),
+        Encoding::DeltaBitPacked => encode_delta_blocks(data),
+        Encoding::RunLength => encode_rle(data),
+        Encoding::Dictionary => encode_dictionary(data),
+        Encoding::ZoneMapped => encode_zone_blocks(data),
+        Encoding::Chunked => encode_chunked(data, &ChunkOptions::default()),
+    }
+}
+
+/// Decodes what `encode_body` produced
+pub(crate) fn decode_body(bytes: &[u8], encoding: Encoding, count: u64) -> Result<Vec<i32>, VectorError> {
+    match encoding {
+        Encoding::Plain => unreachable!("plain data is read by the caller"),
+        Encoding::DeltaBitPacked => decode_delta_blocks(bytes, count),
+        Encoding::RunLength => decode_rle(bytes, count),
+        Encoding::Dictionary => decode_dictionary(bytes, count),
+        Encoding::ZoneMapped => decode_zone_blocks(bytes, count),
+        Encoding::Chunked => decode_chunked(bytes, count, default_threads()),
+    }
+}
+
+/// Number of slices `choose_encoding` samples, and the length of each
+const SAMPLE_SLICES: usize = 16;
+const SAMPLE_SLICE_LEN: usize = 256;
+
+/// Picks the encoding that makes `data` smallest, judging from a sample.
+///
+/// The sample is a handful of contiguous slices spread over the vector, so runs and deltas look
+/// the same as in the full data. Small vectors are measured in full.
+pub fn choose_encoding(data: &[i32]) -> Encoding {
+    let sample: Vec<i32> = if data.len() <= SAMPLE_SLICES * SAMPLE_SLICE_LEN {
+        data.to_vec()
+    } else {
+        let stride = data.len() / SAMPLE_SLICES;
+        (0..SAMPLE_SLICES)
+            .flat_map(|i| &data[i * stride..i * stride + SAMPLE_SLICE_LEN])
+            .copied()
+            .collect()
+    };
+
+    let mut best = (Encoding::Plain, 4 * sample.len());
+    for encoding in [Encoding::DeltaBitPacked, Encoding::RunLength, Encoding::Dictionary] {
+        let size = encode_body(&sample, encoding).len();
+        if size < best.1 {
+            best = (encoding, size);
+        }
+    }
+    best.0
+}
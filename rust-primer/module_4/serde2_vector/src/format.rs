@@ -0,0 +1,339 @@
+use std::fmt;
+use std::io::Error;
+
+// Every vector file starts with a fixed 16-byte header:
+//
+//   offset  size  field
+//   0       4     magic "SRDV"
+//   4       1     format version (1)
+//   5       1     element type code (see ElementType)
+//   6       1     endianness of the elements (0 = little, 1 = big)
+//   7       1     encoding of the elements (see Encoding)
+//   8       8     element count (u64, little-endian)
+//
+// In the plain encoding the elements follow right after the header. 16 is a multiple of every
+// element width, so the data is aligned whenever the file itself is. Other encodings store their
+// own structure after the header and can only be read as a whole.
+//
+// The count is the commit point for appends: new elements are written (and synced) past the end
+// first, and only then is the count rewritten. Bytes after the last counted element are therefore
+// the remains of an interrupted append and are ignored by readers.
+
+/// Identifies a vector file
+pub const MAGIC: [u8; 4] = *b"SRDV";
+
+/// The only layout version this code knows how to read
+pub const VERSION: u8 = 1;
+
+/// Size of the header in bytes; the elements start at this offset
+pub const HEADER_LEN: usize = 16;
+
+/// Offset of the element count within the header
+pub const COUNT_OFFSET: u64 = 8;
+
+/// Byte order of the elements stored in a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// The byte order of the machine running the program
+    pub fn native() -> Self {
+        if cfg!(target_endian = "big") {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+
+    /// Decodes one 4-byte element stored in this byte order
+    pub fn read_i32(self, bytes: [u8; 4]) -> i32 {
+        match self {
+            Endianness::Little => i32::from_le_bytes(bytes),
+            Endianness::Big => i32::from_be_bytes(bytes),
+        }
+    }
+
+    /// Encodes one element in this byte order
+    pub fn write_i32(self, value: i32) -> [u8; 4] {
+        match self {
+            Endianness::Little => value.to_le_bytes(),
+            Endianness::Big => value.to_be_bytes(),
+        }
+    }
+
+    /// Decodes a run of back-to-back 4-byte elements
+    pub fn read_i32s(self, bytes: &[u8]) -> Vec<i32> {
+        bytes.chunks_exact(4).map(|chunk| self.read_i32(chunk.try_into().unwrap())).collect()
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Endianness::Little => 0,
+            Endianness::Big => 1,
+        }
+    }
+
+    fn from_code(code: u8) -> Result<Self, VectorError> {
+        match code {
+            0 => Ok(Endianness::Little),
+            1 => Ok(Endianness::Big),
+            other => Err(VectorError::InvalidEndianness(other)),
+        }
+    }
+}
+
+/// The type of the elements stored in a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+    I32,
+    U8,
+    U16,
+    U32,
+    U64,
+    I64,
+    F32,
+    F64,
+    /// Packed eight to a byte, least significant bit first
+    Bool,
+    /// Variable-length UTF-8 strings (see `varlen`)
+    Utf8,
+    /// Variable-length byte blobs (see `varlen`)
+    Binary,
+}
+
+impl ElementType {
+    /// Width of one element in bytes, rounded up to a whole byte for bools. Strings and blobs
+    /// report the width of one entry in their offsets array.
+    pub fn width(self) -> usize {
+        match self {
+            ElementType::U8 | ElementType::Bool => 1,
+            ElementType::U16 => 2,
+            ElementType::I32 | ElementType::U32 | ElementType::F32 => 4,
+            ElementType::U64 | ElementType::I64 | ElementType::F64 | ElementType::Utf8 | ElementType::Binary => 8,
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            ElementType::I32 => 1,
+            ElementType::U8 => 2,
+            ElementType::U16 => 3,
+            ElementType::U32 => 4,
+            ElementType::U64 => 5,
+            ElementType::I64 => 6,
+            ElementType::F32 => 7,
+            ElementType::F64 => 8,
+            ElementType::Bool => 9,
+            ElementType::Utf8 => 10,
+            ElementType::Binary => 11,
+        }
+    }
+
+    fn from_code(code: u8) -> Result<Self, VectorError> {
+        match code {
+            1 => Ok(ElementType::I32),
+            2 => Ok(ElementType::U8),
+            3 => Ok(ElementType::U16),
+            4 => Ok(ElementType::U32),
+            5 => Ok(ElementType::U64),
+            6 => Ok(ElementType::I64),
+            7 => Ok(ElementType::F32),
+            8 => Ok(ElementType::F64),
+            9 => Ok(ElementType::Bool),
+            10 => Ok(ElementType::Utf8),
+            11 => Ok(ElementType::Binary),
+            other => Err(VectorError::UnknownElementType(other)),
+        }
+    }
+}
+
+/// How the elements are laid out after the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Fixed-width elements back to back; supports random access, memory mapping and appends
+    Plain,
+    /// Blocks of delta + bit-packed elements (see `compress`); best for sorted data
+    DeltaBitPacked,
+    /// (value, run length) pairs (see `encoding`); best for long stretches of equal values
+    RunLength,
+    /// Distinct values once plus bit-packed indices (see `encoding`); best for few distinct values
+    Dictionary,
+    /// Plain little-endian blocks plus a footer of per-block min and max (see `zone_map`); lets
+    /// scans skip blocks that cannot match
+    ZoneMapped,
+    /// Independently encoded chunks behind a table of offsets (see `chunked`); encodes and
+    /// decodes on several threads
+    Chunked,
+}
+
+impl Encoding {
+    pub(crate) fn code(self) -> u8 {
+        match self {
+            Encoding::Plain => 0,
+            Encoding::DeltaBitPacked => 1,
+            Encoding::RunLength => 2,
+            Encoding::Dictionary => 3,
+            Encoding::ZoneMapped => 4,
+            Encoding::Chunked => 5,
+        }
+    }
+
+    pub(crate) fn from_code(code: u8) -> Result<Self, VectorError> {
+        match code {
+            0 => Ok(Encoding::Plain),
+            1 => Ok(Encoding::DeltaBitPacked),
+            2 => Ok(Encoding::RunLength),
+            3 => Ok(Encoding::Dictionary),
+            4 => Ok(Encoding::ZoneMapped),
+            5 => Ok(Encoding::Chunked),
+            other => Err(VectorError::UnknownEncoding(other)),
+        }
+    }
+}
+
+/// The decoded contents of a file header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub element_type: ElementType,
+    pub endianness: Endianness,
+    pub encoding: Encoding,
+    pub count: u64,
+}
+
+impl Header {
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4] = VERSION;
+        bytes[5] = self.element_type.code();
+        bytes[6] = self.endianness.code();
+        bytes[7] = self.encoding.code();
+        bytes[8..16].copy_from_slice(&self.count.to_le_bytes());
+        bytes
+    }
+
+    /// Parses and validates a header from the first `HEADER_LEN` bytes of `bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VectorError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(VectorError::Truncated { expected: HEADER_LEN as u64, found: bytes.len() as u64 });
+        }
+        if bytes[0..4] != MAGIC {
+            return Err(VectorError::BadMagic);
+        }
+        if bytes[4] != VERSION {
+            return Err(VectorError::UnsupportedVersion(bytes[4]));
+        }
+        let element_type = ElementType::from_code(bytes[5])?;
+        let endianness = Endianness::from_code(bytes[6])?;
+        let encoding = Encoding::from_code(bytes[7])?;
+        let count = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        Ok(Header { element_type, endianness, encoding, count })
+    }
+
+    /// Number of bytes the elements take after the header, or `None` if that overflows. For
+    /// strings and blobs this is only the offsets array; the data buffer after it is as long as
+    /// the last offset says.
+    pub fn data_len(&self) -> Option<u64> {
+        match self.element_type {
+            ElementType::Bool => Some(self.count.div_ceil(8)),
+            ElementType::Utf8 | ElementType::Binary => self.count.checked_add(1)?.checked_mul(8),
+            other => self.count.checked_mul(other.width() as u64),
+        }
+    }
+
+    /// Fails unless the elements are stored plain, which operations that compute element offsets
+    /// (random access, memory mapping, appends) rely on
+    pub fn require_plain(&self) -> Result<(), VectorError> {
+        match self.encoding {
+            Encoding::Plain => Ok(()),
+            other => Err(VectorError::EncodingNotSupported(other)),
+        }
+    }
+
+    /// Length of a plain file up to and including the last counted element
+    pub fn committed_len(&self) -> Option<u64> {
+        self.data_len().and_then(|len| len.checked_add(HEADER_LEN as u64))
+    }
+
+    /// Checks that a file of `file_len` bytes holds all the elements this header announces.
+    /// Anything after them is an uncommitted append and not an error.
+    pub fn check_file_len(&self, file_len: u64) -> Result<(), VectorError> {
+        let expected = self.committed_len().ok_or(VectorError::Truncated { expected: u64::MAX, found: file_len })?;
+        if file_len < expected {
+            return Err(VectorError::Truncated { expected, found: file_len });
+        }
+        Ok(())
+    }
+}
+
+/// Everything that can go wrong reading a vector file
+#[derive(Debug)]
+pub enum VectorError {
+    /// The file could not be read or written
+    Io(Error),
+    /// The file does not start with the vector magic bytes, so it is not a vector file
+    BadMagic,
+    /// The file was written with a format version this code cannot read
+    UnsupportedVersion(u8),
+    /// The element type code is not one we know
+    UnknownElementType(u8),
+    /// The file holds elements of a different type than the caller asked for
+    WrongElementType { expected: ElementType, found: ElementType },
+    /// The endianness flag is neither little (0) nor big (1)
+    InvalidEndianness(u8),
+    /// The encoding code is not one we know
+    UnknownEncoding(u8),
+    /// The operation only works on plain files, but this one uses another encoding
+    EncodingNotSupported(Encoding),
+    /// The encoded data is inconsistent
+    Corrupt(&'static str),
+    /// String element `index` is not valid UTF-8
+    InvalidUtf8 { index: u64 },
+    /// The file is shorter than its header says it should be
+    Truncated { expected: u64, found: u64 },
+    /// The elements are stored in a byte order other than this machine's, so they cannot be
+    /// used in place
+    ForeignEndianness(Endianness),
+    /// The elements do not start at an address suitably aligned for their type
+    Misaligned,
+    /// A read asked for elements `start..end` of a vector with only `len` elements
+    OutOfRange { start: u64, end: u64, len: u64 },
+}
+
+impl fmt::Display for VectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VectorError::Io(e) => write!(f, "I/O error: {}", e),
+            VectorError::BadMagic => write!(f, "not a vector file (bad magic bytes)"),
+            VectorError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            VectorError::UnknownElementType(code) => write!(f, "unknown element type code {}", code),
+            VectorError::WrongElementType { expected, found } => {
+                write!(f, "expected elements of type {:?} but the file holds {:?}", expected, found)
+            }
+            VectorError::InvalidEndianness(code) => write!(f, "invalid endianness flag {}", code),
+            VectorError::UnknownEncoding(code) => write!(f, "unknown encoding code {}", code),
+            VectorError::EncodingNotSupported(e) => write!(f, "not supported for {:?} encoded files", e),
+            VectorError::Corrupt(reason) => write!(f, "corrupt data: {}", reason),
+            VectorError::InvalidUtf8 { index } => write!(f, "string {} is not valid UTF-8", index),
+            VectorError::Truncated { expected, found } => {
+                write!(f, "file is truncated: expected {} bytes, found {}", expected, found)
+            }
+            VectorError::ForeignEndianness(e) => write!(f, "elements are {:?} endian, not native", e),
+            VectorError::Misaligned => write!(f, "elements are not aligned in memory"),
+            VectorError::OutOfRange { start, end, len } => {
+                write!(f, "elements {}..{} are out of range for a vector of length {}", start, end, len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VectorError {}
+
+impl From<Error> for VectorError {
+    fn from(e: Error) -> Self {
+        VectorError::Io(e)
+    }
+}
@@ -1,9 +1,12 @@
+#[allow(clippy::single_component_path_imports)]
 use rand;
 use rand::Rng;
 
 use solution::{serialize_data_to_disk, deserialize_data_from_disk};
 
 
+// The starter code is kept as handed out, lints and all
+#[allow(unused_variables, clippy::explicit_counter_loop, clippy::needless_borrow)]
 fn main() {
     println!("Serializing and Deserializing vectors");
     // Change this variable to serialize data (true) or deserialize it (false)
@@ -20,10 +23,10 @@ fn main() {
             data.push(counter);
             counter += 1;
         }
-        serialize_data_to_disk(data, &filename);
+        serialize_data_to_disk(data, &filename).unwrap();
     }
     else{
-        let data = deserialize_data_from_disk(&filename);
+        let data = deserialize_data_from_disk(&filename).unwrap();
         println!("The size of the array is: {}", data.len());
         println!("This is the data:");
         for i in data.iter() {
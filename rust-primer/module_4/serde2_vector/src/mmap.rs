@@ -0,0 +1,61 @@
+use std::fs::File;
+use std::mem::align_of;
+use std::ops::Deref;
+
+use memmap2::Mmap;
+
+use crate::format::{ElementType, Endianness, Header, VectorError, HEADER_LEN};
+
+// Instead of copying the file into a Vec, we ask the OS to map it into our address space and
+// view the bytes after the header directly as a &[i32]. Opening is O(1) no matter how big the
+// file is, and pages are only read from disk when they are touched. This only works when the
+// bytes on disk already are valid i32s for this machine: same endianness, properly aligned.
+
+/// A read-only, zero-copy view of a vector file
+pub struct MappedVector {
+    mmap: Mmap,
+    len: usize,
+}
+
+impl MappedVector {
+    /// Maps `filename` into memory after validating its header, length, endianness and alignment.
+    ///
+    /// As with any memory map, the file must not be modified (e.g. truncated) while it is mapped.
+    pub fn open(filename: &str) -> Result<Self, VectorError> {
+        let file = File::open(filename)?;
+        // Safety: the map is read-only; callers are told not to modify the file while it is open
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let header = Header::from_bytes(&mmap)?;
+        if header.element_type != ElementType::I32 {
+            return Err(VectorError::WrongElementType { expected: ElementType::I32, found: header.element_type });
+        }
+        header.require_plain()?;
+        header.check_file_len(mmap.len() as u64)?;
+        if header.endianness != Endianness::native() {
+            return Err(VectorError::ForeignEndianness(header.endianness));
+        }
+        if mmap[HEADER_LEN..].as_ptr().align_offset(align_of::<i32>()) != 0 {
+            return Err(VectorError::Misaligned);
+        }
+        // The length check above means the count fits in memory, and therefore in a usize
+        let len = header.count as usize;
+        Ok(MappedVector { mmap, len })
+    }
+
+    /// The elements, borrowed straight from the mapped file
+    pub fn as_slice(&self) -> &[i32] {
+        let data = &self.mmap[HEADER_LEN..];
+        // Safety: `open` checked that the data is aligned for i32, holds at least `len` elements
+        // and uses native byte order; every bit pattern is a valid i32
+        unsafe { std::slice::from_raw_parts(data.as_ptr() as *const i32, self.len) }
+    }
+}
+
+impl Deref for MappedVector {
+    type Target = [i32];
+
+    fn deref(&self) -> &[i32] {
+        self.as_slice()
+    }
+}
@@ -1,12 +1,144 @@
+pub mod chunked;
+pub mod compress;
+pub mod element;
+pub mod encoding;
+pub mod format;
+pub mod mmap;
+pub mod varlen;
+pub mod vector_file;
+pub mod zone_map;
+
 use std::fs::File;
-use std::io::{BufReader, Read, Write};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::io::Error;
 
+pub use chunked::{encode_chunked, decode_chunked, default_threads, ChunkOptions};
+pub use compress::{encode_delta_blocks, decode_delta_blocks, decode_block, BLOCK_LEN};
+pub use element::Element;
+pub use encoding::{encode_rle, decode_rle, encode_dictionary, decode_dictionary, choose_encoding};
+pub use format::{ElementType, Encoding, Endianness, Header, VectorError, COUNT_OFFSET, HEADER_LEN, MAGIC, VERSION};
+pub use mmap::MappedVector;
+pub use varlen::{serialize_varlen_to_disk, deserialize_varlen_from_disk, deserialize_varlen_from_bytes, VarlenElement, VarlenFile};
+pub use vector_file::{AppendStep, VectorFile};
+pub use zone_map::{encode_zone_blocks, decode_zone_blocks, BlockStats, Predicate, ZoneMappedVector, ZONE_LEN};
 
+/// Writes `data` to `filename` as a header followed by the elements in native byte order
 pub fn serialize_data_to_disk(data: Vec<i32>, filename: &str) -> Result<(), Error> {
-    panic!("TODO milestone primer-mod5");
+    serialize_data_to_disk_with_endianness(&data, filename, Endianness::native())
+}
+
+/// Writes `data` to `filename` with the elements in the given byte order
+pub fn serialize_data_to_disk_with_endianness(data: &[i32], filename: &str, endianness: Endianness) -> Result<(), Error> {
+    serialize_vector_to_disk_with_endianness(data, filename, endianness)
+}
+
+/// Writes a vector of any supported element type to `filename`, in native byte order, recording
+/// the type in the header
+pub fn serialize_vector_to_disk<T: Element>(data: &[T], filename: &str) -> Result<(), Error> {
+    serialize_vector_to_disk_with_endianness(data, filename, Endianness::native())
+}
+
+/// Like `serialize_vector_to_disk`, with the elements in the given byte order
+pub fn serialize_vector_to_disk_with_endianness<T: Element>(
+    data: &[T],
+    filename: &str,
+    endianness: Endianness,
+) -> Result<(), Error> {
+    let header = Header { element_type: T::TYPE, endianness, encoding: Encoding::Plain, count: data.len() as u64 };
+    let mut bytes = header.to_bytes().to_vec();
+    T::encode_plain(data, endianness, &mut bytes);
+    let mut writer = BufWriter::new(File::create(filename)?);
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+/// Writes `data` to `filename` using the given encoding
+pub fn serialize_data_to_disk_with_encoding(data: &[i32], filename: &str, encoding: Encoding) -> Result<(), Error> {
+    if encoding == Encoding::Plain {
+        return serialize_data_to_disk_with_endianness(data, filename, Endianness::native());
+    }
+    let body = encoding::encode_body(data, encoding);
+    // Encoded data defines their own (little-endian) byte order
+    let header = Header { element_type: ElementType::I32, endianness: Endianness::Little, encoding, count: data.len() as u64 };
+    let mut writer = BufWriter::new(File::create(filename)?);
+    writer.write_all(&header.to_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+/// Writes `data` to `filename` in whichever encoding `choose_encoding` finds smallest, and
+/// returns that encoding. The file records it, so reading needs no hints.
+pub fn serialize_data_to_disk_auto(data: &[i32], filename: &str) -> Result<Encoding, Error> {
+    let encoding = choose_encoding(data);
+    serialize_data_to_disk_with_encoding(data, filename, encoding)?;
+    Ok(encoding)
+}
+
+/// Writes `data` to `filename` as independently encoded chunks, encoding them on
+/// `options.threads` threads
+pub fn serialize_data_to_disk_parallel(data: &[i32], filename: &str, options: &ChunkOptions) -> Result<(), Error> {
+    let body = encode_chunked(data, options);
+    let header = Header { element_type: ElementType::I32, endianness: Endianness::Little, encoding: Encoding::Chunked, count: data.len() as u64 };
+    let mut writer = BufWriter::new(File::create(filename)?);
+    writer.write_all(&header.to_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+/// Reads a chunked file, decoding its chunks on `threads` threads. Files in any other encoding
+/// are read as by `deserialize_data_from_disk`.
+pub fn deserialize_data_from_disk_parallel(filename: &str, threads: usize) -> Result<Vec<i32>, VectorError> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(filename)?).read_to_end(&mut bytes)?;
+    let header = Header::from_bytes(&bytes)?;
+    if header.element_type != ElementType::I32 {
+        return Err(VectorError::WrongElementType { expected: ElementType::I32, found: header.element_type });
+    }
+    match header.encoding {
+        Encoding::Chunked => decode_chunked(&bytes[HEADER_LEN..], header.count, threads),
+        _ => deserialize_data_from_bytes(&bytes),
+    }
+}
+
+/// Reads a file written by any of the `serialize_data_to_disk` functions, checking every header
+/// field and the length
+pub fn deserialize_data_from_disk(filename: &str) -> Result<Vec<i32>, VectorError> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(filename)?).read_to_end(&mut bytes)?;
+    deserialize_data_from_bytes(&bytes)
+}
+
+/// Same as `deserialize_data_from_disk`, for a file that is already in memory
+pub fn deserialize_data_from_bytes(bytes: &[u8]) -> Result<Vec<i32>, VectorError> {
+    deserialize_vector_from_bytes(bytes)
+}
+
+/// Reads a vector of `T`s from `filename`. A file holding another element type is a
+/// `WrongElementType` error; its bytes are never reinterpreted.
+pub fn deserialize_vector_from_disk<T: Element>(filename: &str) -> Result<Vec<T>, VectorError> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(filename)?).read_to_end(&mut bytes)?;
+    deserialize_vector_from_bytes(&bytes)
+}
+
+/// Same as `deserialize_vector_from_disk`, for a file that is already in memory
+pub fn deserialize_vector_from_bytes<T: Element>(bytes: &[u8]) -> Result<Vec<T>, VectorError> {
+    let header = Header::from_bytes(bytes)?;
+    if header.element_type != T::TYPE {
+        return Err(VectorError::WrongElementType { expected: T::TYPE, found: header.element_type });
+    }
+    match header.encoding {
+        Encoding::Plain => {
+            header.check_file_len(bytes.len() as u64)?;
+            // check_file_len guarantees the committed elements are all there; ignore anything after them
+            let end = header.committed_len().unwrap() as usize;
+            T::decode_plain(&bytes[HEADER_LEN..end], header.count as usize, header.endianness)
+        }
+        other => T::decode_encoded(&bytes[HEADER_LEN..], other, header.count),
+    }
 }
 
-pub fn deserialize_data_from_disk(filename: &str) -> Vec<i32> {
-    panic!("TODO milestone primer-mod5");
+/// Appends `data` to the vector stored in `filename` without rewriting the existing elements
+pub fn append_data_to_disk(data: &[i32], filename: &str) -> Result<(), VectorError> {
+    VectorFile::open_writable(filename)?.append(data)
 }
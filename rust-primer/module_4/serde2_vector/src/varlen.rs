@@ -0,0 +1,200 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+
+use crate::format::{ElementType, Encoding, Endianness, Header, VectorError, HEADER_LEN};
+use crate::vector_file::read_up_to;
+
+// Strings and byte blobs use the Arrow layout: all the bytes go into one data buffer, and an
+// offsets array says where each element starts and ends in it.
+//
+//   offsets    (count + 1) * u64   offsets[i]..offsets[i + 1] is element i; offsets[0] is 0
+//   data       offsets[count] bytes
+//
+// The offsets follow the byte order in the header. Element i is found by reading two offsets at a
+// known position and then exactly its bytes, however many elements come before it.
+
+/// A variable-length type that can be stored in a vector file
+pub trait VarlenElement: Sized {
+    /// The type code written to the header
+    const TYPE: ElementType;
+
+    fn as_bytes(&self) -> &[u8];
+
+    /// Builds element `index` from its bytes
+    fn from_bytes(bytes: Vec<u8>, index: u64) -> Result<Self, VectorError>;
+}
+
+impl VarlenElement for String {
+    const TYPE: ElementType = ElementType::Utf8;
+
+    fn as_bytes(&self) -> &[u8] {
+        self.as_bytes()
+    }
+
+    fn from_bytes(bytes: Vec<u8>, index: u64) -> Result<Self, VectorError> {
+        String::from_utf8(bytes).map_err(|_| VectorError::InvalidUtf8 { index })
+    }
+}
+
+impl VarlenElement for Vec<u8> {
+    const TYPE: ElementType = ElementType::Binary;
+
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn from_bytes(bytes: Vec<u8>, _index: u64) -> Result<Self, VectorError> {
+        Ok(bytes)
+    }
+}
+
+fn read_u64(endianness: Endianness, bytes: &[u8]) -> u64 {
+    let bytes = bytes.try_into().unwrap();
+    match endianness {
+        Endianness::Little => u64::from_le_bytes(bytes),
+        Endianness::Big => u64::from_be_bytes(bytes),
+    }
+}
+
+fn write_u64(endianness: Endianness, value: u64) -> [u8; 8] {
+    match endianness {
+        Endianness::Little => value.to_le_bytes(),
+        Endianness::Big => value.to_be_bytes(),
+    }
+}
+
+/// Reads and checks the header of a strings or blobs file holding `T`s
+fn check_header<T: VarlenElement>(bytes: &[u8]) -> Result<Header, VectorError> {
+    let header = Header::from_bytes(bytes)?;
+    if header.element_type != T::TYPE {
+        return Err(VectorError::WrongElementType { expected: T::TYPE, found: header.element_type });
+    }
+    header.require_plain()?;
+    Ok(header)
+}
+
+/// Length of the whole file a header and last offset describe
+fn file_len(header: &Header, last_offset: u64) -> Result<u64, VectorError> {
+    header.committed_len().and_then(|len| len.checked_add(last_offset)).ok_or(VectorError::Corrupt("offsets too large"))
+}
+
+/// Writes strings or blobs to `filename` as offsets plus one data buffer, in native byte order
+pub fn serialize_varlen_to_disk<T: VarlenElement>(data: &[T], filename: &str) -> Result<(), Error> {
+    let endianness = Endianness::native();
+    let header = Header { element_type: T::TYPE, endianness, encoding: Encoding::Plain, count: data.len() as u64 };
+    let mut writer = BufWriter::new(File::create(filename)?);
+    writer.write_all(&header.to_bytes())?;
+    let mut offset = 0u64;
+    writer.write_all(&write_u64(endianness, offset))?;
+    for element in data {
+        offset += element.as_bytes().len() as u64;
+        writer.write_all(&write_u64(endianness, offset))?;
+    }
+    for element in data {
+        writer.write_all(element.as_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Reads every string or blob in `filename`
+pub fn deserialize_varlen_from_disk<T: VarlenElement>(filename: &str) -> Result<Vec<T>, VectorError> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(filename)?).read_to_end(&mut bytes)?;
+    deserialize_varlen_from_bytes(&bytes)
+}
+
+/// Same as `deserialize_varlen_from_disk`, for a file that is already in memory
+pub fn deserialize_varlen_from_bytes<T: VarlenElement>(bytes: &[u8]) -> Result<Vec<T>, VectorError> {
+    let header = check_header::<T>(bytes)?;
+    header.check_file_len(bytes.len() as u64)?;
+    let data_start = header.committed_len().unwrap() as usize;
+    let offsets: Vec<u64> = bytes[HEADER_LEN..data_start].chunks_exact(8).map(|b| read_u64(header.endianness, b)).collect();
+
+    let expected = file_len(&header, offsets[offsets.len() - 1])?;
+    if (bytes.len() as u64) < expected {
+        return Err(VectorError::Truncated { expected, found: bytes.len() as u64 });
+    }
+    if bytes.len() as u64 != expected {
+        return Err(VectorError::Corrupt("unexpected bytes after the data buffer"));
+    }
+    if offsets[0] != 0 {
+        return Err(VectorError::Corrupt("first offset is not zero"));
+    }
+    let data = &bytes[data_start..];
+    offsets
+        .windows(2)
+        .enumerate()
+        .map(|(i, w)| {
+            if w[0] > w[1] || w[1] > data.len() as u64 {
+                return Err(VectorError::Corrupt("element offsets out of bounds"));
+            }
+            T::from_bytes(data[w[0] as usize..w[1] as usize].to_vec(), i as u64)
+        })
+        .collect()
+}
+
+/// An open strings or blobs file that reads single elements on demand
+pub struct VarlenFile<T> {
+    file: File,
+    header: Header,
+    data_start: u64,
+    data_len: u64,
+    element: PhantomData<T>,
+}
+
+impl<T: VarlenElement> VarlenFile<T> {
+    /// Opens `filename`, checking its header and total length without reading any elements. Like
+    /// `deserialize_varlen_from_bytes`, it rejects bytes after the data buffer.
+    pub fn open(filename: &str) -> Result<Self, VectorError> {
+        let mut file = File::open(filename)?;
+        let mut header_bytes = [0u8; HEADER_LEN];
+        let read = read_up_to(&mut file, &mut header_bytes)?;
+        let header = check_header::<T>(&header_bytes[..read])?;
+        let file_len = file.metadata()?.len();
+        header.check_file_len(file_len)?;
+
+        let data_start = header.committed_len().unwrap();
+        let mut last = [0u8; 8];
+        file.seek(SeekFrom::Start(data_start - 8))?;
+        file.read_exact(&mut last)?;
+        let data_len = read_u64(header.endianness, &last);
+        let expected = self::file_len(&header, data_len)?;
+        if file_len < expected {
+            return Err(VectorError::Truncated { expected, found: file_len });
+        }
+        if file_len != expected {
+            return Err(VectorError::Corrupt("unexpected bytes after the data buffer"));
+        }
+        Ok(VarlenFile { file, header, data_start, data_len, element: PhantomData })
+    }
+
+    /// Number of elements in the vector
+    pub fn len(&self) -> u64 {
+        self.header.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.count == 0
+    }
+
+    /// Reads element `index`: its two offsets, then its bytes
+    pub fn get(&mut self, index: u64) -> Result<T, VectorError> {
+        if index >= self.header.count {
+            return Err(VectorError::OutOfRange { start: index, end: index.saturating_add(1), len: self.header.count });
+        }
+        let mut offsets = [0u8; 16];
+        self.file.seek(SeekFrom::Start(HEADER_LEN as u64 + 8 * index))?;
+        self.file.read_exact(&mut offsets)?;
+        let start = read_u64(self.header.endianness, &offsets[..8]);
+        let end = read_u64(self.header.endianness, &offsets[8..]);
+        if start > end || end > self.data_len {
+            return Err(VectorError::Corrupt("element offsets out of bounds"));
+        }
+
+        let mut bytes = vec![0u8; (end - start) as usize];
+        self.file.seek(SeekFrom::Start(self.data_start + start))?;
+        self.file.read_exact(&mut bytes)?;
+        T::from_bytes(bytes, index)
+    }
+}
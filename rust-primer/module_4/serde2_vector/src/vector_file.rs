@@ -0,0 +1,130 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Error, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use crate::format::{ElementType, Header, VectorError, COUNT_OFFSET, HEADER_LEN};
+
+// Elements have a fixed width, so element i lives at HEADER_LEN + 4 * i. Reading one element or
+// a range is a seek plus a read of exactly the bytes needed, however large the file is.
+//
+// Appending writes the new elements after the last committed one and syncs them before touching
+// the count in the header. A crash before the count is rewritten leaves the old vector (plus an
+// ignored tail); a crash after leaves the new one. The count is 8 bytes at an aligned offset in
+// the first sector of the file, which disks write all-or-nothing.
+
+/// The points at which `VectorFile::append_with_hook` hands control to its hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendStep {
+    /// The new elements have been written after the old ones (possibly only in the OS cache)
+    DataWritten,
+    /// The new elements are on disk
+    DataSynced,
+    /// The count in the header has been updated (possibly only in the OS cache)
+    CountWritten,
+    /// The new count is on disk; the append is complete
+    CountSynced,
+}
+
+/// An open vector file that reads elements on demand
+pub struct VectorFile {
+    file: File,
+    header: Header,
+}
+
+impl VectorFile {
+    /// Opens `filename` for reading, validating its header and length without reading any elements
+    pub fn open(filename: &str) -> Result<Self, VectorError> {
+        VectorFile::from_file(File::open(filename)?)
+    }
+
+    /// Opens `filename` for reading and appending
+    pub fn open_writable(filename: &str) -> Result<Self, VectorError> {
+        VectorFile::from_file(OpenOptions::new().read(true).write(true).open(filename)?)
+    }
+
+    fn from_file(mut file: File) -> Result<Self, VectorError> {
+        let mut header_bytes = [0u8; HEADER_LEN];
+        let read = read_up_to(&mut file, &mut header_bytes)?;
+        let header = Header::from_bytes(&header_bytes[..read])?;
+        if header.element_type != ElementType::I32 {
+            return Err(VectorError::WrongElementType { expected: ElementType::I32, found: header.element_type });
+        }
+        header.require_plain()?;
+        header.check_file_len(file.metadata()?.len())?;
+        Ok(VectorFile { file, header })
+    }
+
+    /// Number of elements in the vector
+    pub fn len(&self) -> u64 {
+        self.header.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.count == 0
+    }
+
+    /// Reads element `index`
+    pub fn get(&mut self, index: u64) -> Result<i32, VectorError> {
+        let values = self.range(index..index.saturating_add(1))?;
+        Ok(values[0])
+    }
+
+    /// Reads elements `range.start` up to (not including) `range.end`
+    pub fn range(&mut self, range: Range<u64>) -> Result<Vec<i32>, VectorError> {
+        if range.start > range.end || range.end > self.header.count {
+            return Err(VectorError::OutOfRange { start: range.start, end: range.end, len: self.header.count });
+        }
+        let width = self.header.element_type.width() as u64;
+        let mut bytes = vec![0u8; ((range.end - range.start) * width) as usize];
+        self.file.seek(SeekFrom::Start(HEADER_LEN as u64 + range.start * width))?;
+        self.file.read_exact(&mut bytes)?;
+        Ok(self.header.endianness.read_i32s(&bytes))
+    }
+
+    /// Adds `data` at the end of the vector. The handle must come from `open_writable`.
+    pub fn append(&mut self, data: &[i32]) -> Result<(), VectorError> {
+        self.append_with_hook(data, |_| Ok(()))
+    }
+
+    /// Like `append`, but calls `hook` after every step. If the hook returns an error the append
+    /// stops right there, which lets tests simulate a crash between any two steps.
+    pub fn append_with_hook<F>(&mut self, data: &[i32], mut hook: F) -> Result<(), VectorError>
+    where
+        F: FnMut(AppendStep) -> Result<(), Error>,
+    {
+        let committed = self.header.committed_len().unwrap();
+        let new_count = self.header.count + data.len() as u64;
+
+        let mut bytes = Vec::with_capacity(data.len() * self.header.element_type.width());
+        for &value in data {
+            bytes.extend_from_slice(&self.header.endianness.write_i32(value));
+        }
+        // Drop whatever an earlier interrupted append left after the committed elements
+        self.file.set_len(committed)?;
+        self.file.seek(SeekFrom::Start(committed))?;
+        self.file.write_all(&bytes)?;
+        hook(AppendStep::DataWritten)?;
+        self.file.sync_data()?;
+        hook(AppendStep::DataSynced)?;
+
+        self.file.seek(SeekFrom::Start(COUNT_OFFSET))?;
+        self.file.write_all(&new_count.to_le_bytes())?;
+        self.header.count = new_count;
+        hook(AppendStep::CountWritten)?;
+        self.file.sync_data()?;
+        hook(AppendStep::CountSynced)?;
+        Ok(())
+    }
+}
+
+/// Like `read_exact`, but a short file is not an error: returns how many bytes were read
+pub(crate) fn read_up_to(file: &mut File, buf: &mut [u8]) -> Result<usize, VectorError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
@@ -0,0 +1,234 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::format::{ElementType, Encoding, Header, VectorError, HEADER_LEN};
+use crate::vector_file::read_up_to;
+
+// The zone-mapped encoding stores the elements plain (little-endian), split into blocks of
+// ZONE_LEN elements, and follows them with a footer that summarises every block:
+//
+//   blocks      count * i32
+//   footer      one entry per block, in order:
+//                 min     i32   smallest element in the block
+//                 max     i32   largest element in the block
+//                 count   u32   elements in the block (ZONE_LEN, except possibly the last)
+//
+// The number of blocks follows from the count in the header, so the footer starts at a known
+// offset. A scan reads the footer first and then only the blocks whose [min, max] range could
+// hold a match; "any values > 90,000?" over sorted data touches a handful of blocks.
+
+/// Number of elements per zone-mapped block
+pub const ZONE_LEN: usize = 1024;
+
+/// Size of one footer entry in bytes
+pub const ZONE_ENTRY_LEN: usize = 4 + 4 + 4;
+
+/// The summary the footer keeps for one block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockStats {
+    pub min: i32,
+    pub max: i32,
+    pub count: u32,
+}
+
+/// A condition on single elements that can also be checked against a block's min and max
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Predicate {
+    Eq(i32),
+    Lt(i32),
+    Le(i32),
+    Gt(i32),
+    Ge(i32),
+    /// Between the two bounds, both included
+    Between(i32, i32),
+}
+
+impl Predicate {
+    /// Whether `value` satisfies the predicate
+    pub fn matches(self, value: i32) -> bool {
+        match self {
+            Predicate::Eq(x) => value == x,
+            Predicate::Lt(x) => value < x,
+            Predicate::Le(x) => value <= x,
+            Predicate::Gt(x) => value > x,
+            Predicate::Ge(x) => value >= x,
+            Predicate::Between(low, high) => low <= value && value <= high,
+        }
+    }
+
+    /// Whether some value in `min..=max` could satisfy the predicate. `false` means the whole
+    /// block can be skipped.
+    pub fn may_match(self, min: i32, max: i32) -> bool {
+        match self {
+            Predicate::Eq(x) => min <= x && x <= max,
+            Predicate::Lt(x) => min < x,
+            Predicate::Le(x) => min <= x,
+            Predicate::Gt(x) => max > x,
+            Predicate::Ge(x) => max >= x,
+            Predicate::Between(low, high) => low <= high && min <= high && low <= max,
+        }
+    }
+}
+
+/// Encodes `data` as plain blocks followed by the footer
+pub fn encode_zone_blocks(data: &[i32]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(ZONE_LEN);
+    let mut out = Vec::with_capacity(4 * data.len() + ZONE_ENTRY_LEN * blocks);
+    for &value in data {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    for block in data.chunks(ZONE_LEN) {
+        let stats = BlockStats {
+            min: *block.iter().min().unwrap(),
+            max: *block.iter().max().unwrap(),
+            count: block.len() as u32,
+        };
+        out.extend_from_slice(&stats.min.to_le_bytes());
+        out.extend_from_slice(&stats.max.to_le_bytes());
+        out.extend_from_slice(&stats.count.to_le_bytes());
+    }
+    out
+}
+
+/// Length in bytes of the elements and of the footer for `count` elements
+fn zone_lens(count: u64) -> Result<(u64, u64), VectorError> {
+    let too_large = VectorError::Corrupt("element count too large");
+    let data_len = count.checked_mul(4).ok_or(too_large)?;
+    let footer_len = count.div_ceil(ZONE_LEN as u64) * ZONE_ENTRY_LEN as u64;
+    Ok((data_len, footer_len))
+}
+
+/// Parses and validates a footer describing `count` elements
+fn parse_footer(footer: &[u8], count: u64) -> Result<Vec<BlockStats>, VectorError> {
+    let blocks: Vec<BlockStats> = footer
+        .chunks_exact(ZONE_ENTRY_LEN)
+        .map(|entry| BlockStats {
+            min: i32::from_le_bytes(entry[0..4].try_into().unwrap()),
+            max: i32::from_le_bytes(entry[4..8].try_into().unwrap()),
+            count: u32::from_le_bytes(entry[8..12].try_into().unwrap()),
+        })
+        .collect();
+    for (i, stats) in blocks.iter().enumerate() {
+        let expected = (count - (i * ZONE_LEN) as u64).min(ZONE_LEN as u64);
+        if stats.count as u64 != expected {
+            return Err(VectorError::Corrupt("block count does not match the element count"));
+        }
+        if stats.min > stats.max {
+            return Err(VectorError::Corrupt("block min is larger than its max"));
+        }
+    }
+    Ok(blocks)
+}
+
+/// Decodes what `encode_zone_blocks` produced for `count` elements, checking the footer against
+/// the elements
+pub fn decode_zone_blocks(bytes: &[u8], count: u64) -> Result<Vec<i32>, VectorError> {
+    let (data_len, footer_len) = zone_lens(count)?;
+    let expected = data_len.saturating_add(footer_len);
+    if (bytes.len() as u64) < expected {
+        return Err(VectorError::Truncated { expected, found: bytes.len() as u64 });
+    }
+    if bytes.len() as u64 != expected {
+        return Err(VectorError::Corrupt("unexpected bytes after the footer"));
+    }
+    let data: Vec<i32> = bytes[..data_len as usize]
+        .chunks_exact(4)
+        .map(|chunk| i32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    let blocks = parse_footer(&bytes[data_len as usize..], count)?;
+    for (block, stats) in data.chunks(ZONE_LEN).zip(&blocks) {
+        if !block.iter().all(|&v| stats.min <= v && v <= stats.max) {
+            return Err(VectorError::Corrupt("element outside its block's min and max"));
+        }
+    }
+    Ok(data)
+}
+
+/// An open zone-mapped file that keeps the footer in memory and reads blocks on demand
+pub struct ZoneMappedVector {
+    file: File,
+    header: Header,
+    blocks: Vec<BlockStats>,
+    blocks_read: u64,
+    blocks_skipped: u64,
+}
+
+impl ZoneMappedVector {
+    /// Opens `filename` and reads its footer, but none of the elements
+    pub fn open(filename: &str) -> Result<Self, VectorError> {
+        let mut file = File::open(filename)?;
+        let mut header_bytes = [0u8; HEADER_LEN];
+        let read = read_up_to(&mut file, &mut header_bytes)?;
+        let header = Header::from_bytes(&header_bytes[..read])?;
+        if header.element_type != ElementType::I32 {
+            return Err(VectorError::WrongElementType { expected: ElementType::I32, found: header.element_type });
+        }
+        if header.encoding != Encoding::ZoneMapped {
+            return Err(VectorError::EncodingNotSupported(header.encoding));
+        }
+
+        let (data_len, footer_len) = zone_lens(header.count)?;
+        let expected = (HEADER_LEN as u64).saturating_add(data_len).saturating_add(footer_len);
+        let file_len = file.metadata()?.len();
+        if file_len < expected {
+            return Err(VectorError::Truncated { expected, found: file_len });
+        }
+        if file_len != expected {
+            return Err(VectorError::Corrupt("unexpected bytes after the footer"));
+        }
+        let mut footer = vec![0u8; footer_len as usize];
+        file.seek(SeekFrom::Start(HEADER_LEN as u64 + data_len))?;
+        file.read_exact(&mut footer)?;
+        let blocks = parse_footer(&footer, header.count)?;
+        Ok(ZoneMappedVector { file, header, blocks, blocks_read: 0, blocks_skipped: 0 })
+    }
+
+    /// Number of elements in the vector
+    pub fn len(&self) -> u64 {
+        self.header.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.count == 0
+    }
+
+    /// The footer entries, one per block
+    pub fn block_stats(&self) -> &[BlockStats] {
+        &self.blocks
+    }
+
+    /// Returns the position and value of every element matching `pred`, in order. Blocks whose
+    /// min and max rule out a match are not read at all.
+    pub fn scan_where(&mut self, pred: Predicate) -> Result<Vec<(u64, i32)>, VectorError> {
+        let mut matches = Vec::new();
+        let mut bytes = Vec::with_capacity(4 * ZONE_LEN);
+        for (i, stats) in self.blocks.iter().enumerate() {
+            if !pred.may_match(stats.min, stats.max) {
+                self.blocks_skipped += 1;
+                continue;
+            }
+            self.blocks_read += 1;
+            let first = (i * ZONE_LEN) as u64;
+            bytes.resize(4 * stats.count as usize, 0);
+            self.file.seek(SeekFrom::Start(HEADER_LEN as u64 + 4 * first))?;
+            self.file.read_exact(&mut bytes)?;
+            for (j, chunk) in bytes.chunks_exact(4).enumerate() {
+                let value = i32::from_le_bytes(chunk.try_into().unwrap());
+                if pred.matches(value) {
+                    matches.push((first + j as u64, value));
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Number of blocks `scan_where` has skipped thanks to the footer, over all scans so far
+    pub fn blocks_skipped(&self) -> u64 {
+        self.blocks_skipped
+    }
+
+    /// Number of blocks `scan_where` has had to read, over all scans so far
+    pub fn blocks_read(&self) -> u64 {
+        self.blocks_read
+    }
+}
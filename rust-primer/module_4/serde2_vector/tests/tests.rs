@@ -1,19 +1,965 @@
 use solution::{serialize_data_to_disk, deserialize_data_from_disk};
 
 #[test]
+#[allow(unused_variables, clippy::explicit_counter_loop)]
 fn test_serialize_deserialize_data_to_disk() {
     let n1: u32 = 100000;
     let mut counter = 0;
     let mut data = Vec::new();
-    let filename = "data_test.bin";
+    let filename = temp_path("data_test");
 
     for i in 0..n1 {
         data.push(counter);
         counter += 1;
     }
-    serialize_data_to_disk(data, &filename);
+    serialize_data_to_disk(data, &filename).unwrap();
 
-    let data = deserialize_data_from_disk(&filename);
+    let data = deserialize_data_from_disk(&filename).unwrap();
 
     assert_eq!(n1 as usize, data.len());
+    std::fs::remove_file(&filename).unwrap();
+}
+
+/// A path in the system temp directory that is unique to this test process
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("serde2_vector_{}_{}", name, std::process::id()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+mod header {
+    use super::temp_path;
+    use solution::{ElementType, Encoding, Endianness, Header, VectorError, HEADER_LEN,
+                   serialize_data_to_disk, serialize_data_to_disk_with_endianness,
+                   deserialize_data_from_disk, deserialize_data_from_bytes};
+    use std::fs;
+
+    /// Tests run in parallel, so each caller passes its own `name` for the temporary file
+    fn sample_file(name: &str) -> Vec<u8> {
+        let path = temp_path(name);
+        serialize_data_to_disk(vec![0, 1, -2, i32::MAX], &path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_header_layout() {
+        let bytes = sample_file("sample_layout");
+        assert_eq!(bytes.len(), HEADER_LEN + 4 * 4);
+        assert_eq!(&bytes[0..4], b"SRDV");
+        let header = Header::from_bytes(&bytes).unwrap();
+        assert_eq!(header, Header {
+            element_type: ElementType::I32,
+            endianness: Endianness::native(),
+            encoding: Encoding::Plain,
+            count: 4,
+        });
+    }
+
+    #[test]
+    fn test_both_endiannesses_round_trip() {
+        let data = vec![0, 1, -1, 33, i32::MIN, i32::MAX];
+        for endianness in [Endianness::Little, Endianness::Big] {
+            let path = temp_path(&format!("{:?}", endianness));
+            serialize_data_to_disk_with_endianness(&data, &path, endianness).unwrap();
+            assert_eq!(deserialize_data_from_disk(&path).unwrap(), data);
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_empty_vector() {
+        let path = temp_path("empty");
+        serialize_data_to_disk(Vec::new(), &path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), HEADER_LEN as u64);
+        assert_eq!(deserialize_data_from_disk(&path).unwrap(), Vec::<i32>::new());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_header_validation() {
+        let bytes = sample_file("sample_validation");
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert!(matches!(deserialize_data_from_bytes(&bad), Err(VectorError::BadMagic)));
+
+        let mut bad = bytes.clone();
+        bad[4] = 9;
+        assert!(matches!(deserialize_data_from_bytes(&bad), Err(VectorError::UnsupportedVersion(9))));
+
+        let mut bad = bytes.clone();
+        bad[5] = 200;
+        assert!(matches!(deserialize_data_from_bytes(&bad), Err(VectorError::UnknownElementType(200))));
+
+        let mut bad = bytes.clone();
+        bad[6] = 2;
+        assert!(matches!(deserialize_data_from_bytes(&bad), Err(VectorError::InvalidEndianness(2))));
+
+        let mut bad = bytes.clone();
+        bad[7] = 200;
+        assert!(matches!(deserialize_data_from_bytes(&bad), Err(VectorError::UnknownEncoding(200))));
+
+        assert!(matches!(deserialize_data_from_bytes(&bytes[..10]), Err(VectorError::Truncated { expected: 16, found: 10 })));
+        assert!(matches!(deserialize_data_from_bytes(&bytes[..bytes.len() - 1]), Err(VectorError::Truncated { .. })));
+
+        // Bytes after the counted elements are an interrupted append and are ignored
+        let mut long = bytes.clone();
+        long.extend_from_slice(&[0, 0]);
+        assert_eq!(deserialize_data_from_bytes(&long).unwrap(), [0, 1, -2, i32::MAX]);
+
+        // A count so large the byte length overflows
+        let mut bad = bytes.clone();
+        bad[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(deserialize_data_from_bytes(&bad), Err(VectorError::Truncated { .. })));
+    }
+
+    #[test]
+    fn test_missing_file_is_io_error() {
+        assert!(matches!(deserialize_data_from_disk(&temp_path("does_not_exist")), Err(VectorError::Io(_))));
+    }
+}
+
+mod mmap {
+    use super::temp_path;
+    use solution::{Endianness, MappedVector, VectorError,
+                   serialize_data_to_disk, serialize_data_to_disk_with_endianness, deserialize_data_from_disk};
+    use std::fs;
+
+    #[test]
+    fn test_mapped_matches_loaded() {
+        let path = temp_path("mapped");
+        let data: Vec<i32> = (0..100000).collect();
+        serialize_data_to_disk(data.clone(), &path).unwrap();
+
+        let mapped = MappedVector::open(&path).unwrap();
+        assert_eq!(mapped.len(), data.len());
+        assert_eq!(mapped.as_slice(), deserialize_data_from_disk(&path).unwrap().as_slice());
+        assert_eq!(mapped[99999], 99999);
+        assert!(mapped.windows(2).all(|w| w[0] < w[1]));
+        // Scanning borrows from the map: the slice points into it rather than into a copy
+        assert_eq!(mapped.iter().map(|&v| v as i64).sum::<i64>(), 99999 * 100000 / 2);
+        drop(mapped);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mapped_empty() {
+        let path = temp_path("mapped_empty");
+        serialize_data_to_disk(Vec::new(), &path).unwrap();
+        assert!(MappedVector::open(&path).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mapped_rejects_foreign_endianness() {
+        let path = temp_path("mapped_foreign");
+        let foreign = match Endianness::native() {
+            Endianness::Little => Endianness::Big,
+            Endianness::Big => Endianness::Little,
+        };
+        serialize_data_to_disk_with_endianness(&[1, 2, 3], &path, foreign).unwrap();
+        assert!(matches!(MappedVector::open(&path), Err(VectorError::ForeignEndianness(e)) if e == foreign));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mapped_validates_header_and_length() {
+        let path = temp_path("mapped_bad");
+        serialize_data_to_disk(vec![1, 2, 3], &path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.pop();
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(MappedVector::open(&path), Err(VectorError::Truncated { .. })));
+        bytes[0] = 0;
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(MappedVector::open(&path), Err(VectorError::BadMagic)));
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod vector_file {
+    use super::temp_path;
+    use solution::{Endianness, VectorError, VectorFile,
+                   serialize_data_to_disk, serialize_data_to_disk_with_endianness, deserialize_data_from_disk};
+    use std::fs;
+
+    #[test]
+    fn test_random_access_matches_full_load() {
+        let path = temp_path("random_access");
+        let data: Vec<i32> = (0..100000).map(|i| i * 7 - 3).collect();
+        serialize_data_to_disk(data, &path).unwrap();
+        let loaded = deserialize_data_from_disk(&path).unwrap();
+
+        let mut file = VectorFile::open(&path).unwrap();
+        assert_eq!(file.len(), loaded.len() as u64);
+        for i in [0, 1, 4999, 50000, 99998, 99999] {
+            assert_eq!(file.get(i).unwrap(), loaded[i as usize]);
+        }
+        assert_eq!(file.range(0..0).unwrap(), Vec::<i32>::new());
+        assert_eq!(file.range(100..250).unwrap(), &loaded[100..250]);
+        assert_eq!(file.range(99990..100000).unwrap(), &loaded[99990..]);
+        assert_eq!(file.range(0..100000).unwrap(), loaded);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_random_access_big_endian() {
+        let path = temp_path("random_access_be");
+        serialize_data_to_disk_with_endianness(&[10, -20, 30], &path, Endianness::Big).unwrap();
+        let mut file = VectorFile::open(&path).unwrap();
+        assert_eq!(file.get(1).unwrap(), -20);
+        assert_eq!(file.range(1..3).unwrap(), [-20, 30]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let path = temp_path("out_of_range");
+        serialize_data_to_disk(vec![1, 2, 3], &path).unwrap();
+        let mut file = VectorFile::open(&path).unwrap();
+        assert!(matches!(file.get(3), Err(VectorError::OutOfRange { start: 3, end: 4, len: 3 })));
+        assert!(matches!(file.get(u64::MAX), Err(VectorError::OutOfRange { .. })));
+        assert!(matches!(file.range(2..4), Err(VectorError::OutOfRange { .. })));
+        #[allow(clippy::reversed_empty_ranges)]
+        let backwards = file.range(2..1);
+        assert!(matches!(backwards, Err(VectorError::OutOfRange { .. })));
+        // Failed reads leave the handle usable
+        assert_eq!(file.get(2).unwrap(), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_open_validates_file() {
+        let path = temp_path("open_invalid");
+        fs::write(&path, b"SRDV").unwrap();
+        assert!(matches!(VectorFile::open(&path), Err(VectorError::Truncated { .. })));
+        serialize_data_to_disk(vec![1, 2, 3], &path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 4);
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(VectorFile::open(&path), Err(VectorError::Truncated { .. })));
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod append {
+    use super::temp_path;
+    use solution::{AppendStep, Endianness, VectorError, VectorFile,
+                   append_data_to_disk, serialize_data_to_disk, serialize_data_to_disk_with_endianness,
+                   deserialize_data_from_disk};
+    use std::fs::{self, OpenOptions};
+    use std::io::{Error, Write};
+
+    #[test]
+    fn test_append_extends_vector() {
+        let path = temp_path("append");
+        serialize_data_to_disk((0..1000).collect(), &path).unwrap();
+        append_data_to_disk(&[1000, 1001], &path).unwrap();
+        append_data_to_disk(&[], &path).unwrap();
+        append_data_to_disk(&(1002..5000).collect::<Vec<i32>>(), &path).unwrap();
+        assert_eq!(deserialize_data_from_disk(&path).unwrap(), (0..5000).collect::<Vec<i32>>());
+        // Only the new elements were added to the file
+        assert_eq!(fs::metadata(&path).unwrap().len(), 16 + 4 * 5000);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_append_keeps_endianness_and_updates_handle() {
+        let path = temp_path("append_be");
+        serialize_data_to_disk_with_endianness(&[1, 2], &path, Endianness::Big).unwrap();
+        let mut file = VectorFile::open_writable(&path).unwrap();
+        file.append(&[-3]).unwrap();
+        assert_eq!(file.len(), 3);
+        assert_eq!(file.get(2).unwrap(), -3);
+        assert_eq!(deserialize_data_from_disk(&path).unwrap(), [1, 2, -3]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_append_needs_writable_handle() {
+        let path = temp_path("append_readonly");
+        serialize_data_to_disk(vec![1], &path).unwrap();
+        assert!(matches!(VectorFile::open(&path).unwrap().append(&[2]), Err(VectorError::Io(_))));
+        assert_eq!(deserialize_data_from_disk(&path).unwrap(), [1]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_crash_during_append_leaves_old_or_new() {
+        let old: Vec<i32> = (0..100).collect();
+        let extra: Vec<i32> = (100..150).collect();
+        let new: Vec<i32> = (0..150).collect();
+        let steps = [AppendStep::DataWritten, AppendStep::DataSynced, AppendStep::CountWritten, AppendStep::CountSynced];
+        for (i, crash_at) in steps.into_iter().enumerate() {
+            let path = temp_path("append_crash");
+            serialize_data_to_disk(old.clone(), &path).unwrap();
+            let result = VectorFile::open_writable(&path).unwrap().append_with_hook(&extra, |step| {
+                if step == crash_at { Err(Error::other("simulated crash")) } else { Ok(()) }
+            });
+            assert!(result.is_err());
+            let expected = if i < 2 { &old } else { &new };
+            assert_eq!(&deserialize_data_from_disk(&path).unwrap(), expected, "crash at {:?}", crash_at);
+
+            // The next append still works and discards the abandoned tail
+            append_data_to_disk(&[-1], &path).unwrap();
+            let mut after = expected.clone();
+            after.push(-1);
+            assert_eq!(deserialize_data_from_disk(&path).unwrap(), after);
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_torn_tail_is_ignored() {
+        // Before the count is updated, the OS may have persisted any prefix of the new elements
+        let path = temp_path("append_torn");
+        serialize_data_to_disk(vec![7, 8, 9], &path).unwrap();
+        for tail_len in 0..12 {
+            let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(&vec![0xab; tail_len]).unwrap();
+            drop(file);
+            assert_eq!(deserialize_data_from_disk(&path).unwrap(), [7, 8, 9]);
+            assert_eq!(VectorFile::open(&path).unwrap().range(0..3).unwrap(), [7, 8, 9]);
+            fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(16 + 12).unwrap();
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod compress {
+    use super::temp_path;
+    use solution::{Encoding, MappedVector, VectorError, VectorFile, BLOCK_LEN,
+                   encode_delta_blocks, decode_delta_blocks, decode_block,
+                   serialize_data_to_disk_with_encoding, deserialize_data_from_disk, append_data_to_disk};
+    use rand::Rng;
+    use std::fs;
+
+    fn round_trip(data: &[i32]) -> usize {
+        let encoded = encode_delta_blocks(data);
+        assert_eq!(decode_delta_blocks(&encoded, data.len() as u64).unwrap(), data);
+        encoded.len()
+    }
+
+    #[test]
+    fn test_delta_round_trip_edge_cases() {
+        round_trip(&[]);
+        round_trip(&[42]);
+        round_trip(&[i32::MIN, i32::MAX, i32::MIN, 0, i32::MAX]);
+        round_trip(&[5; 1000]);
+        round_trip(&(0..BLOCK_LEN as i32 + 1).rev().collect::<Vec<i32>>());
+        let mut rng = rand::thread_rng();
+        for len in [1, 2, 127, 128, 129, 1000] {
+            round_trip(&(0..len).map(|_| rng.gen()).collect::<Vec<i32>>());
+            round_trip(&(0..len).map(|_| rng.gen_range(-3..3)).collect::<Vec<i32>>());
+        }
+    }
+
+    #[test]
+    fn test_blocks_decode_independently() {
+        let data: Vec<i32> = (0..1000).map(|i| i * i).collect();
+        let encoded = encode_delta_blocks(&data);
+        // Walk the blocks one by one; each one only needs its own bytes
+        let mut offset = 0;
+        let mut block_index = 0;
+        while offset < encoded.len() {
+            let mut block = Vec::new();
+            offset += decode_block(&encoded[offset..], &mut block).unwrap();
+            let start = block_index * BLOCK_LEN;
+            assert_eq!(block, &data[start..(start + BLOCK_LEN).min(data.len())]);
+            block_index += 1;
+        }
+        assert_eq!(block_index, data.len().div_ceil(BLOCK_LEN));
+    }
+
+    #[test]
+    fn test_compression_ratio() {
+        let counter: Vec<i32> = (0..100000).collect();
+        let mut rng = rand::thread_rng();
+        let random: Vec<i32> = (0..100000).map(|_| rng.gen()).collect();
+        let plain_len = 4 * counter.len();
+
+        let counter_ratio = plain_len as f64 / round_trip(&counter) as f64;
+        let random_ratio = plain_len as f64 / round_trip(&random) as f64;
+        println!("compression ratio: counter {:.1}x, random {:.2}x", counter_ratio, random_ratio);
+        assert!(counter_ratio > 30.0);
+        // Random data does not compress, but the block overhead keeps it close to plain
+        assert!(random_ratio > 0.9);
+    }
+
+    #[test]
+    fn test_compressed_file_round_trip() {
+        let path = temp_path("compressed");
+        let data: Vec<i32> = (0..100000).collect();
+        serialize_data_to_disk_with_encoding(&data, &path, Encoding::DeltaBitPacked).unwrap();
+        assert!(fs::metadata(&path).unwrap().len() < 20000);
+        assert_eq!(deserialize_data_from_disk(&path).unwrap(), data);
+
+        // Offsets of individual elements are unknown, so the plain-only readers refuse
+        assert!(matches!(VectorFile::open(&path), Err(VectorError::EncodingNotSupported(Encoding::DeltaBitPacked))));
+        assert!(matches!(MappedVector::open(&path), Err(VectorError::EncodingNotSupported(_))));
+        assert!(matches!(append_data_to_disk(&[1], &path), Err(VectorError::EncodingNotSupported(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_blocks() {
+        let data: Vec<i32> = (0..300).collect();
+        let encoded = encode_delta_blocks(&data);
+        assert!(matches!(decode_delta_blocks(&encoded, 301), Err(VectorError::Truncated { .. })));
+        assert!(matches!(decode_delta_blocks(&encoded, 299), Err(VectorError::Corrupt(_))));
+        assert!(matches!(decode_delta_blocks(&encoded[..encoded.len() - 1], 300), Err(VectorError::Truncated { .. })));
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(matches!(decode_delta_blocks(&trailing, 300), Err(VectorError::Corrupt(_))));
+        let mut bad_width = encoded.clone();
+        bad_width[2] = 40;
+        assert!(matches!(decode_delta_blocks(&bad_width, 300), Err(VectorError::Corrupt(_))));
+        let mut bad_count = encoded.clone();
+        bad_count[0..2].copy_from_slice(&0u16.to_le_bytes());
+        assert!(matches!(decode_delta_blocks(&bad_count, 300), Err(VectorError::Corrupt(_))));
+        // A reference near the ends of i64 would overflow the running value
+        for reference in [i64::MAX, i64::MAX - 1, i64::MIN] {
+            let mut bad_reference = encoded.clone();
+            bad_reference[7..15].copy_from_slice(&reference.to_le_bytes());
+            assert!(matches!(decode_delta_blocks(&bad_reference, 300), Err(VectorError::Corrupt("decoded value out of range"))));
+        }
+    }
+}
+
+mod encoding {
+    use super::temp_path;
+    use solution::{Encoding, VectorError, encode_rle, decode_rle, encode_dictionary, decode_dictionary,
+                   choose_encoding, serialize_data_to_disk_auto, serialize_data_to_disk_with_encoding,
+                   deserialize_data_from_disk, deserialize_data_from_bytes};
+    use rand::Rng;
+    use std::fs;
+
+    /// HTTP-style status codes: a few distinct values, mixed together
+    fn status_codes(len: usize) -> Vec<i32> {
+        let mut rng = rand::thread_rng();
+        (0..len).map(|_| [200, 200, 200, 201, 304, 404, 500][rng.gen_range(0..7)]).collect()
+    }
+
+    #[test]
+    fn test_rle_round_trip() {
+        let mut rng = rand::thread_rng();
+        let cases: Vec<Vec<i32>> = vec![
+            vec![],
+            vec![7],
+            vec![i32::MIN, i32::MIN, i32::MAX],
+            [vec![1; 1000], vec![2; 3], vec![1; 500]].concat(),
+            (0..1000).map(|_| rng.gen_range(0..2)).collect(),
+        ];
+        for data in cases {
+            assert_eq!(decode_rle(&encode_rle(&data), data.len() as u64).unwrap(), data);
+        }
+        // Two runs of a million elements take 16 bytes
+        assert_eq!(encode_rle(&[vec![3; 1_000_000], vec![4; 1_000_000]].concat()).len(), 16);
+    }
+
+    #[test]
+    fn test_dictionary_round_trip() {
+        let mut rng = rand::thread_rng();
+        let cases: Vec<Vec<i32>> = vec![
+            vec![],
+            vec![7],
+            vec![5; 100],
+            vec![i32::MIN, i32::MAX, 0, i32::MAX],
+            status_codes(1000),
+            (0..1000).map(|_| rng.gen()).collect(),
+        ];
+        for data in cases {
+            assert_eq!(decode_dictionary(&encode_dictionary(&data), data.len() as u64).unwrap(), data);
+        }
+        // Five distinct values need 3 bits per element
+        let data = status_codes(8000);
+        assert!(encode_dictionary(&data).len() <= 4 + 5 * 4 + 1 + 3000);
+    }
+
+    #[test]
+    fn test_corrupt_rle() {
+        let encoded = encode_rle(&[1, 1, 2]);
+        assert!(matches!(decode_rle(&encoded, 4), Err(VectorError::Truncated { .. })));
+        assert!(matches!(decode_rle(&encoded, 2), Err(VectorError::Corrupt(_))));
+        assert!(matches!(decode_rle(&encoded[..encoded.len() - 1], 3), Err(VectorError::Corrupt(_))));
+        let mut empty_run = encoded;
+        empty_run[4..8].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(decode_rle(&empty_run, 1), Err(VectorError::Corrupt(_))));
+
+        // Long runs and a huge count must be rejected before anything is allocated
+        let long_runs: Vec<u8> = (0..300).flat_map(|_| [7i32.to_le_bytes(), u32::MAX.to_le_bytes()].concat()).collect();
+        assert!(matches!(decode_rle(&long_runs, 1 << 40), Err(VectorError::Corrupt(_))));
+        assert!(matches!(decode_rle(&long_runs[..8], 1 << 40), Err(VectorError::Truncated { .. })));
+        assert!(matches!(decode_rle(&long_runs, u64::MAX), Err(VectorError::Truncated { .. })));
+    }
+
+    #[test]
+    fn test_corrupt_dictionary() {
+        // Three distinct values use 2-bit indices, so index 3 points past the dictionary
+        let encoded = encode_dictionary(&[10, 20, 30, 30]);
+        assert!(matches!(decode_dictionary(&encoded, 5), Err(VectorError::Truncated { .. })));
+        assert!(matches!(decode_dictionary(&encoded, 2), Err(VectorError::Corrupt(_))));
+        assert!(matches!(decode_dictionary(&encoded[..3], 4), Err(VectorError::Truncated { .. })));
+        let mut bad_index = encoded.clone();
+        *bad_index.last_mut().unwrap() = 0xff;
+        assert!(matches!(decode_dictionary(&bad_index, 4), Err(VectorError::Corrupt(_))));
+        let mut bad_width = encoded.clone();
+        bad_width[16] = 7;
+        assert!(matches!(decode_dictionary(&bad_width, 4), Err(VectorError::Corrupt(_))));
+        let mut huge = encoded;
+        huge[0..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(decode_dictionary(&huge, 4), Err(VectorError::Corrupt(_))));
+
+        // One value means 0-bit indices, so any count fits in 9 bytes; an impossible one must fail
+        let single = encode_dictionary(&[5, 5, 5]);
+        assert_eq!(single.len(), 4 + 4 + 1);
+        assert_eq!(decode_dictionary(&single, 3).unwrap(), [5, 5, 5]);
+        assert!(matches!(decode_dictionary(&single, u64::MAX), Err(VectorError::Corrupt(_))));
+    }
+
+    #[test]
+    fn test_corrupt_count_in_file() {
+        for encoding in [Encoding::RunLength, Encoding::Dictionary] {
+            let path = temp_path(&format!("corrupt_count_{:?}", encoding));
+            serialize_data_to_disk_with_encoding(&[5; 3], &path, encoding).unwrap();
+            let mut bytes = fs::read(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+            bytes[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+            assert!(deserialize_data_from_bytes(&bytes).is_err(), "{:?}", encoding);
+        }
+    }
+
+    #[test]
+    fn test_choose_encoding() {
+        let mut rng = rand::thread_rng();
+        let counter: Vec<i32> = (0..100000).collect();
+        let random: Vec<i32> = (0..100000).map(|_| rng.gen()).collect();
+        let runs: Vec<i32> = (0..100000).map(|i| i / 5000 * 37 % 11).collect();
+        assert_eq!(choose_encoding(&counter), Encoding::DeltaBitPacked);
+        assert_eq!(choose_encoding(&random), Encoding::Plain);
+        assert_eq!(choose_encoding(&runs), Encoding::RunLength);
+        assert_eq!(choose_encoding(&status_codes(100000)), Encoding::Dictionary);
+        assert_eq!(choose_encoding(&[]), Encoding::Plain);
+    }
+
+    #[test]
+    fn test_auto_file_round_trip() {
+        let path = temp_path("auto");
+        let mut rng = rand::thread_rng();
+        let inputs: Vec<Vec<i32>> = vec![
+            (0..100000).collect(),
+            (0..100000).map(|_| rng.gen()).collect(),
+            vec![9; 100000],
+            status_codes(100000),
+        ];
+        for data in inputs {
+            let encoding = serialize_data_to_disk_auto(&data, &path).unwrap();
+            // The chosen encoding is never bigger than plain, and the reader needs no hints
+            assert!(fs::metadata(&path).unwrap().len() <= 16 + 4 * data.len() as u64);
+            assert_eq!(deserialize_data_from_disk(&path).unwrap(), data, "{:?}", encoding);
+        }
+        for encoding in [Encoding::RunLength, Encoding::Dictionary] {
+            let data = status_codes(1000);
+            serialize_data_to_disk_with_encoding(&data, &path, encoding).unwrap();
+            assert_eq!(deserialize_data_from_disk(&path).unwrap(), data);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod zone_map {
+    use super::temp_path;
+    use solution::{Encoding, Predicate, VectorError, ZoneMappedVector, ZONE_LEN, encode_zone_blocks, decode_zone_blocks,
+                   serialize_data_to_disk_with_encoding, deserialize_data_from_disk};
+    use rand::Rng;
+    use std::fs;
+
+    #[test]
+    fn test_scan_skips_blocks() {
+        let path = temp_path("zone_scan");
+        let data: Vec<i32> = (0..100000).collect();
+        serialize_data_to_disk_with_encoding(&data, &path, Encoding::ZoneMapped).unwrap();
+        let mut vector = ZoneMappedVector::open(&path).unwrap();
+        assert_eq!(vector.len(), 100000);
+        assert_eq!(vector.block_stats().len(), data.len().div_ceil(ZONE_LEN));
+
+        let found = vector.scan_where(Predicate::Gt(90000)).unwrap();
+        assert_eq!(found.len(), 9999);
+        assert_eq!(found[0], (90001, 90001));
+        // Only the block holding 90,000 and the ones after it are read
+        let read = data.len().div_ceil(ZONE_LEN) - 90000 / ZONE_LEN;
+        assert_eq!(vector.blocks_read(), read as u64);
+        assert_eq!(vector.blocks_skipped(), (90000 / ZONE_LEN) as u64);
+
+        // Nothing is larger than the maximum, so nothing is read
+        assert!(vector.scan_where(Predicate::Gt(100000)).unwrap().is_empty());
+        assert_eq!(vector.blocks_read(), read as u64);
+        assert_eq!(vector.blocks_skipped(), (90000 / ZONE_LEN + data.len().div_ceil(ZONE_LEN)) as u64);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_scan_matches_full_filter() {
+        let path = temp_path("zone_filter");
+        let mut rng = rand::thread_rng();
+        let data: Vec<i32> = (0..10000).map(|i| i / 10 + rng.gen_range(-50..50)).collect();
+        serialize_data_to_disk_with_encoding(&data, &path, Encoding::ZoneMapped).unwrap();
+        let mut vector = ZoneMappedVector::open(&path).unwrap();
+        let predicates = [
+            Predicate::Eq(500), Predicate::Lt(10), Predicate::Le(10), Predicate::Gt(990),
+            Predicate::Ge(990), Predicate::Between(300, 310), Predicate::Between(10, 5),
+        ];
+        for pred in predicates {
+            let expected: Vec<(u64, i32)> =
+                data.iter().enumerate().filter(|(_, &v)| pred.matches(v)).map(|(i, &v)| (i as u64, v)).collect();
+            assert_eq!(vector.scan_where(pred).unwrap(), expected, "{:?}", pred);
+        }
+        assert!(vector.blocks_skipped() > 0);
+        assert_eq!(deserialize_data_from_disk(&path).unwrap(), data);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_zone_round_trip_edge_cases() {
+        for len in [0, 1, ZONE_LEN - 1, ZONE_LEN, ZONE_LEN + 1] {
+            let data: Vec<i32> = (0..len as i32).map(|i| i.wrapping_mul(-7919)).collect();
+            assert_eq!(decode_zone_blocks(&encode_zone_blocks(&data), len as u64).unwrap(), data);
+        }
+        let path = temp_path("zone_empty");
+        serialize_data_to_disk_with_encoding(&[], &path, Encoding::ZoneMapped).unwrap();
+        let mut vector = ZoneMappedVector::open(&path).unwrap();
+        assert!(vector.is_empty());
+        assert!(vector.scan_where(Predicate::Ge(i32::MIN)).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_footer() {
+        let data: Vec<i32> = (0..2000).collect();
+        let encoded = encode_zone_blocks(&data);
+        let footer = 4 * data.len();
+        assert!(matches!(decode_zone_blocks(&encoded[..encoded.len() - 1], 2000), Err(VectorError::Truncated { .. })));
+        assert!(matches!(decode_zone_blocks(&encoded, 1999), Err(VectorError::Corrupt(_))));
+        // A max below the block's real maximum would make scans skip matches
+        let mut bad_max = encoded.clone();
+        bad_max[footer + 4..footer + 8].copy_from_slice(&5i32.to_le_bytes());
+        assert!(matches!(decode_zone_blocks(&bad_max, 2000), Err(VectorError::Corrupt(_))));
+        let mut bad_count = encoded;
+        bad_count[footer + 8..footer + 12].copy_from_slice(&7u32.to_le_bytes());
+        assert!(matches!(decode_zone_blocks(&bad_count, 2000), Err(VectorError::Corrupt(_))));
+
+        let path = temp_path("zone_corrupt");
+        serialize_data_to_disk_with_encoding(&data, &path, Encoding::ZoneMapped).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.pop();
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(ZoneMappedVector::open(&path), Err(VectorError::Truncated { .. })));
+        serialize_data_to_disk_with_encoding(&data, &path, Encoding::Plain).unwrap();
+        assert!(matches!(ZoneMappedVector::open(&path), Err(VectorError::EncodingNotSupported(Encoding::Plain))));
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod element_types {
+    use super::temp_path;
+    use solution::{Element, ElementType, Encoding, Endianness, VectorError, serialize_vector_to_disk,
+                   serialize_vector_to_disk_with_endianness, deserialize_vector_from_disk, deserialize_vector_from_bytes,
+                   serialize_data_to_disk_with_encoding, deserialize_data_from_disk};
+    use std::fmt::Debug;
+    use std::fs;
+
+    fn round_trip<T: Element + PartialEq + Debug>(name: &str, data: &[T]) {
+        let path = temp_path(name);
+        for endianness in [Endianness::Little, Endianness::Big] {
+            serialize_vector_to_disk_with_endianness(data, &path, endianness).unwrap();
+            assert_eq!(deserialize_vector_from_disk::<T>(&path).unwrap(), data);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_every_type_round_trips() {
+        round_trip("type_u8", &[0u8, 1, 255]);
+        round_trip("type_u16", &[0u16, 1, u16::MAX]);
+        round_trip("type_u32", &[0u32, 1, u32::MAX]);
+        round_trip("type_u64", &[0u64, 1, u64::MAX]);
+        round_trip("type_i32", &[i32::MIN, -1, 0, i32::MAX]);
+        round_trip("type_i64", &[i64::MIN, -1, 0, i64::MAX]);
+        round_trip("type_f32", &[0.0f32, -0.0, 1.5, f32::MIN_POSITIVE, f32::INFINITY, f32::NEG_INFINITY]);
+        round_trip("type_f64", &[0.0f64, -0.0, 1e300, f64::EPSILON, f64::INFINITY]);
+        round_trip::<u64>("type_empty", &[]);
+    }
+
+    #[test]
+    fn test_nan_keeps_its_bits() {
+        let path = temp_path("type_nan");
+        let data = [f64::NAN, f64::from_bits(0x7ff8_0000_dead_beef), -f64::NAN];
+        serialize_vector_to_disk(&data, &path).unwrap();
+        let read = deserialize_vector_from_disk::<f64>(&path).unwrap();
+        assert_eq!(read.iter().map(|v| v.to_bits()).collect::<Vec<u64>>(), data.map(f64::to_bits));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bools_are_bit_packed() {
+        let path = temp_path("type_bool");
+        for len in [0usize, 1, 7, 8, 9, 1000] {
+            let data: Vec<bool> = (0..len).map(|i| i % 3 == 0).collect();
+            serialize_vector_to_disk(&data, &path).unwrap();
+            assert_eq!(fs::metadata(&path).unwrap().len(), 16 + len.div_ceil(8) as u64);
+            assert_eq!(deserialize_vector_from_disk::<bool>(&path).unwrap(), data);
+        }
+
+        // Ten bools use two bytes; the six unused bits of the second one must stay zero
+        serialize_vector_to_disk(&[true; 10], &path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[17] |= 0x80;
+        assert!(matches!(deserialize_vector_from_bytes::<bool>(&bytes), Err(VectorError::Corrupt(_))));
+        bytes.truncate(17);
+        assert!(matches!(deserialize_vector_from_bytes::<bool>(&bytes), Err(VectorError::Truncated { .. })));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_wrong_type_is_an_error() {
+        let path = temp_path("type_wrong");
+        serialize_vector_to_disk(&[1u64, 2, 3], &path).unwrap();
+        // Same width, different type: the bytes would decode, but must not be reinterpreted
+        assert!(matches!(
+            deserialize_vector_from_disk::<f64>(&path),
+            Err(VectorError::WrongElementType { expected: ElementType::F64, found: ElementType::U64 })
+        ));
+        assert!(matches!(deserialize_vector_from_disk::<i64>(&path), Err(VectorError::WrongElementType { .. })));
+        assert!(matches!(deserialize_data_from_disk(&path), Err(VectorError::WrongElementType { .. })));
+
+        serialize_vector_to_disk(&[true, false], &path).unwrap();
+        assert!(matches!(deserialize_vector_from_disk::<u8>(&path), Err(VectorError::WrongElementType { .. })));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_compressed_files_read_as_i32_only() {
+        let path = temp_path("type_compressed");
+        let data: Vec<i32> = (0..1000).collect();
+        serialize_data_to_disk_with_encoding(&data, &path, Encoding::DeltaBitPacked).unwrap();
+        assert_eq!(deserialize_vector_from_disk::<i32>(&path).unwrap(), data);
+        assert!(matches!(deserialize_vector_from_disk::<u32>(&path), Err(VectorError::WrongElementType { .. })));
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod varlen {
+    use super::temp_path;
+    use solution::{ElementType, VarlenFile, VectorError, serialize_varlen_to_disk, deserialize_varlen_from_disk,
+                   deserialize_varlen_from_bytes, deserialize_data_from_disk};
+    use std::fs;
+
+    fn words() -> Vec<String> {
+        ["", "a", "hello", "héllo wörld", "🦀", ""].iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_strings_round_trip() {
+        let path = temp_path("varlen_strings");
+        serialize_varlen_to_disk(&words(), &path).unwrap();
+        assert_eq!(deserialize_varlen_from_disk::<String>(&path).unwrap(), words());
+        serialize_varlen_to_disk::<String>(&[], &path).unwrap();
+        assert!(deserialize_varlen_from_disk::<String>(&path).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_blobs_round_trip() {
+        let path = temp_path("varlen_blobs");
+        let blobs: Vec<Vec<u8>> = vec![vec![], vec![0xff, 0xfe], (0..=255).collect(), vec![0; 10000]];
+        serialize_varlen_to_disk(&blobs, &path).unwrap();
+        // Header, five offsets, then the bytes themselves
+        let data_len: usize = blobs.iter().map(Vec::len).sum();
+        assert_eq!(fs::metadata(&path).unwrap().len(), (16 + 5 * 8 + data_len) as u64);
+        assert_eq!(deserialize_varlen_from_disk::<Vec<u8>>(&path).unwrap(), blobs);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_get_reads_one_element() {
+        let path = temp_path("varlen_get");
+        let data: Vec<String> = (0..10000).map(|i| "x".repeat(i % 50) + &i.to_string()).collect();
+        serialize_varlen_to_disk(&data, &path).unwrap();
+        let mut file = VarlenFile::<String>::open(&path).unwrap();
+        assert_eq!(file.len(), 10000);
+        for i in [9999, 0, 4321, 1] {
+            assert_eq!(file.get(i).unwrap(), data[i as usize]);
+        }
+        assert!(matches!(file.get(10000), Err(VectorError::OutOfRange { .. })));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let path = temp_path("varlen_utf8");
+        let blobs: Vec<Vec<u8>> = vec![b"fine".to_vec(), vec![0xc3, 0x28], b"also fine".to_vec()];
+        serialize_varlen_to_disk(&blobs, &path).unwrap();
+        // Patch the type code so the blobs claim to be strings
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[5] = 10;
+        assert!(matches!(deserialize_varlen_from_bytes::<String>(&bytes), Err(VectorError::InvalidUtf8 { index: 1 })));
+        fs::write(&path, &bytes).unwrap();
+        let mut file = VarlenFile::<String>::open(&path).unwrap();
+        assert_eq!(file.get(0).unwrap(), "fine");
+        assert!(matches!(file.get(1), Err(VectorError::InvalidUtf8 { index: 1 })));
+        assert_eq!(file.get(2).unwrap(), "also fine");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_wrong_type_and_corruption() {
+        let path = temp_path("varlen_corrupt");
+        serialize_varlen_to_disk(&words(), &path).unwrap();
+        assert!(matches!(
+            deserialize_varlen_from_disk::<Vec<u8>>(&path),
+            Err(VectorError::WrongElementType { expected: ElementType::Binary, found: ElementType::Utf8 })
+        ));
+        assert!(matches!(deserialize_data_from_disk(&path), Err(VectorError::WrongElementType { .. })));
+
+        let bytes = fs::read(&path).unwrap();
+        assert!(matches!(deserialize_varlen_from_bytes::<String>(&bytes[..bytes.len() - 1]), Err(VectorError::Truncated { .. })));
+        assert!(matches!(deserialize_varlen_from_bytes::<String>(&bytes[..30]), Err(VectorError::Truncated { .. })));
+        let mut trailing = bytes.clone();
+        trailing.push(b'!');
+        assert!(matches!(deserialize_varlen_from_bytes::<String>(&trailing), Err(VectorError::Corrupt(_))));
+        // Both readers reject the same trailing byte
+        fs::write(&path, &trailing).unwrap();
+        assert!(matches!(VarlenFile::<String>::open(&path), Err(VectorError::Corrupt(_))));
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(VarlenFile::<String>::open(&path), Err(VectorError::Truncated { .. })));
+        // Make the second offset point past the data buffer
+        let mut bad_offset = bytes;
+        bad_offset[24..32].copy_from_slice(&1000u64.to_ne_bytes());
+        assert!(matches!(deserialize_varlen_from_bytes::<String>(&bad_offset), Err(VectorError::Corrupt(_))));
+        fs::write(&path, &bad_offset).unwrap();
+        assert!(matches!(VarlenFile::<String>::open(&path).unwrap().get(0), Err(VectorError::Corrupt(_))));
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod chunked {
+    use super::temp_path;
+    use solution::{ChunkOptions, Encoding, VectorError, encode_chunked, decode_chunked, serialize_data_to_disk_parallel,
+                   deserialize_data_from_disk_parallel, deserialize_data_from_disk, serialize_data_to_disk_with_encoding};
+    use rand::Rng;
+    use std::fs;
+    use std::time::Instant;
+
+    /// Sorted, random and low-cardinality stretches, so chunks end up in different encodings
+    fn mixed_data(len: usize) -> Vec<i32> {
+        let mut rng = rand::thread_rng();
+        (0..len)
+            .map(|i| match (i / 5000) % 3 {
+                0 => i as i32,
+                1 => rng.gen(),
+                _ => rng.gen_range(0..4),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let data = mixed_data(100000);
+        for chunk_len in [1, 1000, 4096, 100000, 1 << 20] {
+            let data = if chunk_len == 1 { &data[..3000] } else { &data[..] };
+            let sequential = encode_chunked(data, &ChunkOptions { chunk_len, threads: 1 });
+            for threads in [2, 3, 8] {
+                // Encoding is deterministic, so every thread count writes the same bytes
+                assert_eq!(encode_chunked(data, &ChunkOptions { chunk_len, threads }), sequential);
+                assert_eq!(decode_chunked(&sequential, data.len() as u64, threads).unwrap(), data);
+            }
+            assert_eq!(decode_chunked(&sequential, data.len() as u64, 1).unwrap(), data);
+        }
+        let empty = encode_chunked(&[], &ChunkOptions { chunk_len: 10, threads: 4 });
+        assert!(decode_chunked(&empty, 0, 4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_chunked_file_round_trip() {
+        let path = temp_path("chunked");
+        let data = mixed_data(50000);
+        serialize_data_to_disk_parallel(&data, &path, &ChunkOptions { chunk_len: 5000, threads: 4 }).unwrap();
+        // Sorted and low-cardinality chunks compress, random ones stay plain
+        assert!(fs::metadata(&path).unwrap().len() < 4 * 50000 / 2);
+        assert_eq!(deserialize_data_from_disk_parallel(&path, 4).unwrap(), data);
+        assert_eq!(deserialize_data_from_disk(&path).unwrap(), data);
+
+        serialize_data_to_disk_with_encoding(&data, &path, Encoding::Chunked).unwrap();
+        assert_eq!(deserialize_data_from_disk_parallel(&path, 2).unwrap(), data);
+        serialize_data_to_disk_with_encoding(&data, &path, Encoding::Plain).unwrap();
+        assert_eq!(deserialize_data_from_disk_parallel(&path, 2).unwrap(), data);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_chunks() {
+        let data = mixed_data(20000);
+        let encoded = encode_chunked(&data, &ChunkOptions { chunk_len: 5000, threads: 1 });
+        let count = data.len() as u64;
+        for threads in [1, 4] {
+            assert!(matches!(decode_chunked(&encoded, count + 1, threads), Err(VectorError::Corrupt(_))));
+            assert!(matches!(decode_chunked(&encoded[..encoded.len() - 1], count, threads), Err(VectorError::Truncated { .. })));
+            assert!(matches!(decode_chunked(&encoded[..40], count, threads), Err(VectorError::Truncated { .. })));
+            let mut trailing = encoded.clone();
+            trailing.push(0);
+            assert!(matches!(decode_chunked(&trailing, count, threads), Err(VectorError::Corrupt(_))));
+            let mut bad_encoding = encoded.clone();
+            bad_encoding[16 + 8 * 5] = 5;
+            assert!(matches!(decode_chunked(&bad_encoding, count, threads), Err(VectorError::Corrupt(_))));
+            let mut bad_offset = encoded.clone();
+            bad_offset[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+            assert!(matches!(decode_chunked(&bad_offset, count, threads), Err(VectorError::Corrupt(_))));
+            let mut no_len = encoded.clone();
+            no_len[0..8].copy_from_slice(&0u64.to_le_bytes());
+            assert!(matches!(decode_chunked(&no_len, count, threads), Err(VectorError::Corrupt(_))));
+        }
+    }
+
+    #[test]
+    fn test_huge_count_in_one_chunk() {
+        // A count and chunk length that agree with each other but not with the chunk bytes must
+        // fail before the output is allocated
+        let mut rng = rand::thread_rng();
+        let counter: Vec<i32> = (0..1000).collect();
+        let random: Vec<i32> = (0..1000).map(|_| rng.gen()).collect();
+        let runs: Vec<i32> = (0..1000).map(|i| i / 100).collect();
+        let few: Vec<i32> = (0..1000).map(|_| [1, 5, 9][rng.gen_range(0..3)]).collect();
+        for data in [counter, random, runs, few] {
+            let mut encoded = encode_chunked(&data, &ChunkOptions { chunk_len: 1 << 20, threads: 1 });
+            assert_eq!(decode_chunked(&encoded, 1000, 1).unwrap(), data);
+            encoded[0..8].copy_from_slice(&(1u64 << 40).to_le_bytes());
+            assert!(matches!(decode_chunked(&encoded, 1 << 40, 1), Err(VectorError::Corrupt("chunk too short for its elements"))));
+        }
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_` to see how throughput
+    /// scales with the number of threads
+    #[test]
+    #[ignore]
+    fn bench_throughput_by_thread_count() {
+        let data = mixed_data(50_000_000);
+        let megabytes = 4.0 * data.len() as f64 / 1e6;
+        let options = |threads| ChunkOptions { chunk_len: 1 << 20, threads };
+        let encoded = encode_chunked(&data, &options(1));
+        println!("threads  encode MB/s  decode MB/s");
+        for threads in [1, 2, 4, 8] {
+            let start = Instant::now();
+            let again = encode_chunked(&data, &options(threads));
+            let encode = megabytes / start.elapsed().as_secs_f64();
+            let start = Instant::now();
+            let decoded = decode_chunked(&encoded, data.len() as u64, threads).unwrap();
+            let decode = megabytes / start.elapsed().as_secs_f64();
+            assert_eq!(again, encoded);
+            assert_eq!(decoded, data);
+            println!("{:>7}  {:>11.0}  {:>11.0}", threads, encode, decode);
+        }
+    }
 }